@@ -0,0 +1,18 @@
+//! Character components
+
+use bevy::prelude::*;
+use bevy_inspector_egui::Inspectable;
+
+/// Lets a character fall through one-way platforms
+#[derive(Default, Component, Inspectable)]
+pub struct DropThrough {
+    /// How long until the character lands on one-way platforms again (seconds)
+    pub remaining: f32,
+}
+
+/// A crouching character
+#[derive(Default, Component, Inspectable)]
+pub struct Crouching {
+    /// The collider half extents to restore when the character stands back up
+    pub standing_half_extents: Vec2,
+}
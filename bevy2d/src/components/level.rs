@@ -0,0 +1,187 @@
+//! Level components
+
+use bevy::prelude::*;
+use bevy_inspector_egui::Inspectable;
+
+use crate::level::{CharacterOverrides, PathMode, PlatformPath};
+
+/// Marks entities that are owned by the current level
+#[derive(Default, Component, Inspectable)]
+pub struct LevelEntity;
+
+/// Level trigger volume
+#[derive(Default, Component, Inspectable)]
+pub struct LevelTrigger {
+    /// The trigger name
+    pub name: String,
+}
+
+/// Marks a level trigger as the level exit
+#[derive(Default, Component, Inspectable)]
+pub struct LevelExit;
+
+/// Marks a level trigger as climbable
+#[derive(Default, Component, Inspectable)]
+pub struct Climbable;
+
+/// A volume of water that characters swim in
+#[derive(Component, Inspectable)]
+pub struct Water {
+    /// How much of gravity the water pushes back up against (1 floats)
+    pub buoyancy: f32,
+
+    /// How quickly the water slows things down
+    pub drag: f32,
+}
+
+impl Default for Water {
+    fn default() -> Self {
+        Self {
+            buoyancy: 0.8,
+            drag: 2.0,
+        }
+    }
+}
+
+/// Marks a level trigger as hurting characters that touch it
+#[derive(Default, Component, Inspectable)]
+pub struct Hazard;
+
+/// Marks a static level platform
+#[derive(Default, Component, Inspectable)]
+pub struct LevelPlatform;
+
+/// Level spawn point
+///
+/// These are only visible while editing the level
+#[derive(Default, Component)]
+pub struct SpawnPoint {
+    /// The prefab spawned here
+    pub prefab: String,
+
+    /// Character tuning overrides
+    pub character: CharacterOverrides,
+}
+
+/// Marks a platform that characters can pass through from below
+#[derive(Default, Component, Inspectable)]
+pub struct OneWayPlatform;
+
+/// A kinematic platform that follows a path
+#[derive(Component)]
+pub struct MovingPlatform {
+    /// The path to follow
+    pub path: PlatformPath,
+
+    /// Where the path starts, the waypoints are relative to this
+    pub origin: Vec2,
+
+    /// How fast the platform is moving, for carrying characters
+    pub velocity: Vec2,
+
+    from: usize,
+    to: usize,
+    progress: f32,
+    reverse: bool,
+    finished: bool,
+}
+
+impl MovingPlatform {
+    /// Creates a moving platform at the start of its path
+    pub fn new(origin: Vec2, path: PlatformPath) -> Self {
+        Self {
+            path,
+            origin,
+            velocity: Vec2::ZERO,
+            from: 0,
+            to: 1,
+            progress: 0.0,
+            reverse: false,
+            finished: false,
+        }
+    }
+
+    fn point_count(&self) -> usize {
+        self.path.waypoints.len() + 1
+    }
+
+    fn point(&self, idx: usize) -> Vec2 {
+        match idx {
+            0 => self.origin,
+            idx => self.origin + self.path.waypoints[idx - 1],
+        }
+    }
+
+    /// Picks the next waypoint to move towards
+    ///
+    /// Returns false if the end of the path has been reached
+    fn next_segment(&mut self) -> bool {
+        let last = self.point_count() - 1;
+
+        let next = match self.path.mode {
+            PathMode::Once => {
+                if self.to == last {
+                    return false;
+                }
+                self.to + 1
+            }
+            PathMode::PingPong => {
+                if (self.reverse && self.to == 0) || (!self.reverse && self.to == last) {
+                    self.reverse = !self.reverse;
+                }
+
+                if self.reverse {
+                    self.to - 1
+                } else {
+                    self.to + 1
+                }
+            }
+            PathMode::Loop => (self.to + 1) % (last + 1),
+        };
+
+        self.from = self.to;
+        self.to = next;
+        true
+    }
+
+    /// Returns where the platform should be
+    pub fn position(&self) -> Vec2 {
+        if self.point_count() < 2 {
+            return self.origin;
+        }
+
+        self.point(self.from)
+            .lerp(self.point(self.to), self.path.easing.ease(self.progress))
+    }
+
+    /// Moves the platform along its path
+    ///
+    /// Returns where the platform should be
+    pub fn advance(&mut self, dt: f32) -> Vec2 {
+        if self.point_count() < 2 || self.finished {
+            return self.position();
+        }
+
+        let mut distance = self.path.speed * dt;
+
+        // don't get stuck if every waypoint is in the same place
+        for _ in 0..self.point_count() * 2 {
+            let length = self.point(self.from).distance(self.point(self.to));
+            let segment_remaining = (1.0 - self.progress) * length;
+            if distance < segment_remaining {
+                self.progress += distance / length;
+                break;
+            }
+
+            distance -= segment_remaining;
+            if !self.next_segment() {
+                self.progress = 1.0;
+                self.finished = true;
+                break;
+            }
+            self.progress = 0.0;
+        }
+
+        self.position()
+    }
+}
@@ -1,3 +1,6 @@
 //! ECS components
 
+pub mod character;
 pub mod game;
+pub mod level;
+pub mod world;
@@ -0,0 +1,9 @@
+//! World components
+
+use bevy::prelude::*;
+use bevy_inspector_egui::Inspectable;
+
+/// The duplicate sprite drawn on the opposite edge of the world
+/// while a wrapping entity straddles the edge
+#[derive(Default, Component, Inspectable)]
+pub struct WrapGhost;
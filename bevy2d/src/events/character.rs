@@ -1,4 +1,42 @@
 //! Character events
 
+use bevy::prelude::*;
+
 /// Jump!
 pub struct JumpEvent;
+
+/// Stop jumping!
+pub struct JumpReleaseEvent;
+
+/// A character got hit
+pub struct HitEvent {
+    /// The character that got hit
+    pub character: Entity,
+
+    /// Where the hit came from, the character is knocked back away from here
+    pub source: Vec2,
+}
+
+/// A character entered the water
+pub struct WaterEnterEvent {
+    /// The character
+    pub character: Entity,
+
+    /// The water volume
+    pub water: Entity,
+}
+
+/// A character left the water
+pub struct WaterExitEvent {
+    /// The character
+    pub character: Entity,
+
+    /// The water volume
+    pub water: Entity,
+}
+
+/// Dash!
+pub struct DashEvent {
+    /// The dash direction, zero to dash the way the character is moving
+    pub direction: Vec2,
+}
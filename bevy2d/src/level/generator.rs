@@ -0,0 +1,333 @@
+//! Procedural level generation
+//!
+//! Platforms are generated one at a time, each one within jumping distance
+//! of a platform that's already known to be reachable from the ground,
+//! so every platform in the level can be reached
+
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use super::*;
+use crate::resources::world::WorldBounds2D;
+
+/// How tall the space above a platform has to be for a character to fit
+const CHARACTER_CLEARANCE: f32 = 2.5;
+
+/// How thick generated platforms are
+const PLATFORM_THICKNESS: f32 = 1.0;
+
+/// How many times to try placing each platform before giving up
+const PLACEMENT_ATTEMPTS: usize = 50;
+
+/// How far a character can move and jump
+#[derive(Debug, Copy, Clone)]
+pub struct JumpLimits {
+    /// Horizontal movement speed
+    pub speed: f32,
+
+    /// Initial vertical jump velocity
+    pub jump_velocity: f32,
+
+    /// Downward acceleration while in the air
+    pub gravity: f32,
+}
+
+impl JumpLimits {
+    /// Returns the highest a character can jump
+    pub fn max_height(&self) -> f32 {
+        self.jump_velocity.powi(2) / (2.0 * self.gravity)
+    }
+
+    /// Returns how far a character can move horizontally
+    /// while jumping to a surface `dy` above (or below) them
+    ///
+    /// Returns None if the surface is too high to reach
+    pub fn max_distance(&self, dy: f32) -> Option<f32> {
+        let discriminant = self.jump_velocity.powi(2) - 2.0 * self.gravity * dy;
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        let air_time = (self.jump_velocity + discriminant.sqrt()) / self.gravity;
+        Some(self.speed * air_time)
+    }
+}
+
+/// Procedural level generation settings
+#[derive(Debug, Clone)]
+pub struct GeneratorSettings {
+    /// How many platforms to try and generate
+    pub platform_count: usize,
+
+    /// The narrowest a platform can be
+    pub min_platform_width: f32,
+
+    /// The widest a platform can be
+    pub max_platform_width: f32,
+
+    /// How much of the jump limits to actually use
+    pub safety_factor: f32,
+}
+
+impl Default for GeneratorSettings {
+    fn default() -> Self {
+        Self {
+            platform_count: 12,
+            min_platform_width: 2.0,
+            max_platform_width: 6.0,
+            safety_factor: 0.8,
+        }
+    }
+}
+
+/// The walkable top surface of a platform
+#[derive(Debug, Copy, Clone)]
+struct Surface {
+    left: f32,
+    right: f32,
+    top: f32,
+}
+
+impl Surface {
+    fn overlaps(&self, left: f32, right: f32, bottom: f32, top: f32) -> bool {
+        self.left < right
+            && self.right > left
+            && self.top > bottom
+            && self.top - PLATFORM_THICKNESS < top
+    }
+}
+
+fn random_color(rng: &mut ChaCha8Rng) -> Color {
+    Color::hsl(rng.gen_range(0.0..360.0), 0.8, 0.6)
+}
+
+/// Tries to place a platform reachable from the surface at `from_idx`
+///
+/// `from` may be a narrower section of that surface to jump from
+#[allow(clippy::too_many_arguments)]
+fn place_platform(
+    rng: &mut ChaCha8Rng,
+    from_idx: usize,
+    from: &Surface,
+    surfaces: &[Surface],
+    bounds: &WorldBounds2D,
+    limits: &JumpLimits,
+    settings: &GeneratorSettings,
+) -> Option<Surface> {
+    let max_rise = limits.max_height() * settings.safety_factor;
+    let dy = rng.gen_range(-max_rise * 0.5..=max_rise);
+    let top = from.top + dy;
+    if top - PLATFORM_THICKNESS < bounds.min.y || top + CHARACTER_CLEARANCE > bounds.max.y {
+        return None;
+    }
+
+    let max_gap = limits.max_distance(dy)? * settings.safety_factor;
+    let gap = rng.gen_range(0.0..=max_gap.max(0.0));
+    let width = rng.gen_range(settings.min_platform_width..=settings.max_platform_width);
+
+    // the gap is between the edge of the source and the near edge of the new platform
+    let (left, right, near_edge, far_edge) = if rng.gen_bool(0.5) {
+        let left = from.right + gap;
+        (left, left + width, from.right, left)
+    } else {
+        let right = from.left - gap;
+        (right - width, right, right, from.left)
+    };
+
+    if left < bounds.min.x || right > bounds.max.x {
+        return None;
+    }
+
+    let surface = Surface { left, right, top };
+
+    // leave room to stand on the new platform
+    if surfaces.iter().any(|other| {
+        other.overlaps(
+            left,
+            right,
+            top - PLATFORM_THICKNESS,
+            top + CHARACTER_CLEARANCE,
+        )
+    }) {
+        return None;
+    }
+
+    // and keep the jump path clear
+    let (corridor_left, corridor_right) = (near_edge.min(far_edge), near_edge.max(far_edge));
+    let corridor_bottom = from.top.min(top);
+    let corridor_top = from.top.max(top) + CHARACTER_CLEARANCE;
+    if surfaces.iter().enumerate().any(|(idx, other)| {
+        idx != from_idx
+            && other.overlaps(corridor_left, corridor_right, corridor_bottom, corridor_top)
+    }) {
+        return None;
+    }
+
+    Some(surface)
+}
+
+/// Generates a level inside the given bounds
+///
+/// The same seed always generates the same level
+pub fn generate_level(
+    seed: u64,
+    bounds: WorldBounds2D,
+    limits: JumpLimits,
+    settings: &GeneratorSettings,
+) -> LevelAsset {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+
+    let ground = Surface {
+        left: bounds.min.x,
+        right: bounds.max.x,
+        top: bounds.min.y + PLATFORM_THICKNESS,
+    };
+
+    let mut surfaces = vec![ground];
+    let mut colors = vec![Color::GREEN];
+
+    for _ in 0..settings.platform_count {
+        for _ in 0..PLACEMENT_ATTEMPTS {
+            // the ground is too wide to jump off the edges of
+            // so anything placed from it is placed from a random spot
+            let from_idx = rng.gen_range(0..surfaces.len());
+            let from = match from_idx {
+                0 => {
+                    let x = rng.gen_range(ground.left..ground.right);
+                    Surface {
+                        left: x,
+                        right: x,
+                        top: ground.top,
+                    }
+                }
+                idx => surfaces[idx],
+            };
+
+            if let Some(surface) = place_platform(
+                &mut rng, from_idx, &from, &surfaces, &bounds, &limits, settings,
+            ) {
+                surfaces.push(surface);
+                colors.push(random_color(&mut rng));
+                break;
+            }
+        }
+    }
+
+    let mut level = LevelAsset {
+        name: format!("Generated ({})", seed),
+        bounds: Some(bounds),
+        ..Default::default()
+    };
+
+    for (idx, (surface, color)) in surfaces.iter().zip(colors).enumerate() {
+        let size = Vec2::new(surface.right - surface.left, PLATFORM_THICKNESS);
+        level.platforms.push(PlatformDefinition {
+            name: if idx == 0 {
+                "Ground".to_owned()
+            } else {
+                default_platform_name()
+            },
+            position: Vec2::new(
+                (surface.left + surface.right) / 2.0,
+                surface.top - PLATFORM_THICKNESS / 2.0,
+            ),
+            size,
+            color,
+            collision_groups: LevelCollisionGroups::default(),
+            ..Default::default()
+        });
+    }
+
+    // start on the ground, exit on the last platform placed
+    level.spawns.push(SpawnDefinition {
+        prefab: "player".to_owned(),
+        position: Vec2::new(bounds.min.x + 1.0, ground.top + 1.0),
+        character: CharacterOverrides::default(),
+    });
+
+    if let Some(last) = surfaces.last().filter(|_| surfaces.len() > 1) {
+        level.triggers.push(TriggerDefinition {
+            name: "Exit".to_owned(),
+            kind: TriggerKind::Exit,
+            position: Vec2::new((last.left + last.right) / 2.0, last.top + 1.0),
+            size: Vec2::new(1.0, 2.0),
+        });
+    }
+
+    level
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bounds() -> WorldBounds2D {
+        WorldBounds2D {
+            min: Vec2::new(-17.78, -10.0),
+            max: Vec2::new(17.78, 10.0),
+        }
+    }
+
+    fn limits() -> JumpLimits {
+        JumpLimits {
+            speed: 10.0,
+            jump_velocity: 12.0,
+            gravity: 9.81,
+        }
+    }
+
+    fn layout(level: &LevelAsset) -> Vec<(Vec2, Vec2)> {
+        level
+            .platforms
+            .iter()
+            .map(|platform| (platform.position, platform.size))
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_same_level() {
+        let settings = GeneratorSettings::default();
+        let a = generate_level(1234, bounds(), limits(), &settings);
+        let b = generate_level(1234, bounds(), limits(), &settings);
+
+        assert_eq!(layout(&a), layout(&b));
+        assert_eq!(a.triggers.len(), b.triggers.len());
+        assert_eq!(
+            a.triggers.first().map(|trigger| trigger.position),
+            b.triggers.first().map(|trigger| trigger.position)
+        );
+    }
+
+    #[test]
+    fn different_seeds_different_levels() {
+        let settings = GeneratorSettings::default();
+        let a = generate_level(1, bounds(), limits(), &settings);
+        let b = generate_level(2, bounds(), limits(), &settings);
+
+        assert_ne!(layout(&a), layout(&b));
+    }
+
+    #[test]
+    fn platforms_inside_bounds() {
+        let bounds = bounds();
+        let level = generate_level(42, bounds, limits(), &GeneratorSettings::default());
+
+        assert!(level.platforms.len() > 1);
+        for platform in level.platforms.iter() {
+            let half_size = platform.size / 2.0;
+            assert!(platform.position.x - half_size.x >= bounds.min.x - 1e-4);
+            assert!(platform.position.x + half_size.x <= bounds.max.x + 1e-4);
+            assert!(platform.position.y - half_size.y >= bounds.min.y - 1e-4);
+            assert!(platform.position.y + half_size.y <= bounds.max.y + 1e-4);
+        }
+    }
+
+    #[test]
+    fn max_distance_unreachable_height() {
+        let limits = limits();
+
+        assert!(limits.max_distance(limits.max_height() + 1.0).is_none());
+        assert!(limits.max_distance(0.0).unwrap() > 0.0);
+    }
+}
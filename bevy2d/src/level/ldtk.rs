@@ -0,0 +1,505 @@
+//! LDtk project import
+//!
+//! Every level in the project is loaded as a labeled level asset
+//! (`world.ldtk#Level_0`), the first level is also the default asset
+//!
+//! IntGrid layers become merged static colliders, one set per IntGrid value.
+//! IntGrid values named after a surface material preset (`ice`, `mud`, `bouncy`
+//! or `conveyor`) give their colliders that surface material.
+//! Entities named `Trigger` become trigger volumes, named by their `name` field,
+//! entities named `Exit` become trigger volumes that complete the level,
+//! entities named `Ladder` become climbable trigger volumes,
+//! entities named `Water` become trigger volumes that characters swim in
+//! and entities named `Hazard` become trigger volumes that hurt characters.
+//! Every other entity is a spawn point for the prefab with the lowercased
+//! entity identifier (`Player` spawns the `player` prefab)
+//!
+//! Spawns take the `speed`, `air_control_factor` and `jump_force`
+//! float fields as character overrides
+
+use std::collections::HashMap;
+
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+
+use core_lib::components::world::SurfaceMaterial;
+
+use super::*;
+use crate::resources::world::WorldBounds2D;
+
+fn error(message: impl Into<String>) -> bevy::asset::Error {
+    bevy::asset::Error::msg(message.into())
+}
+
+#[derive(Debug, Deserialize)]
+struct IntGridValueDefinition {
+    value: i32,
+    #[serde(default)]
+    identifier: Option<String>,
+    color: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LayerDefinition {
+    uid: i32,
+    #[serde(default)]
+    int_grid_values: Vec<IntGridValueDefinition>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Definitions {
+    #[serde(default)]
+    layers: Vec<LayerDefinition>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FieldInstance {
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    #[serde(rename = "__value")]
+    value: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EntityInstance {
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    #[serde(rename = "__pivot")]
+    pivot: [f32; 2],
+    px: [f32; 2],
+    width: f32,
+    height: f32,
+    #[serde(default)]
+    field_instances: Vec<FieldInstance>,
+}
+
+impl EntityInstance {
+    fn field(&self, identifier: &str) -> Option<&serde_json::Value> {
+        self.field_instances
+            .iter()
+            .find(|field| field.identifier == identifier)
+            .map(|field| &field.value)
+    }
+
+    fn float_field(&self, identifier: &str) -> Option<f32> {
+        self.field(identifier)
+            .and_then(|value| value.as_f64())
+            .map(|value| value as f32)
+    }
+
+    fn character_overrides(&self) -> CharacterOverrides {
+        CharacterOverrides {
+            speed: self.float_field("speed"),
+            air_control_factor: self.float_field("air_control_factor"),
+            jump_force: self.float_field("jump_force").map(|force| Vec2::Y * force),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LayerInstance {
+    #[serde(rename = "__identifier")]
+    identifier: String,
+    #[serde(rename = "__type")]
+    layer_type: String,
+    #[serde(rename = "__cWid")]
+    c_wid: usize,
+    #[serde(rename = "__cHei")]
+    c_hei: usize,
+    #[serde(rename = "__gridSize")]
+    grid_size: f32,
+    layer_def_uid: i32,
+    #[serde(default)]
+    int_grid_csv: Vec<i32>,
+    #[serde(default)]
+    entity_instances: Vec<EntityInstance>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Neighbour {
+    #[serde(rename = "levelIid")]
+    level_iid: String,
+    dir: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Level {
+    identifier: String,
+    iid: String,
+    world_x: f32,
+    world_y: f32,
+    px_wid: f32,
+    px_hei: f32,
+    #[serde(default)]
+    layer_instances: Option<Vec<LayerInstance>>,
+    #[serde(rename = "__neighbours", default)]
+    neighbours: Vec<Neighbour>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Project {
+    default_grid_size: f32,
+    #[serde(default)]
+    external_levels: bool,
+    defs: Definitions,
+    levels: Vec<Level>,
+}
+
+/// Converts level pixels to world units, centered on the level
+struct LevelSpace {
+    grid_size: f32,
+    size: Vec2,
+}
+
+impl LevelSpace {
+    fn to_world(&self, px: Vec2) -> Vec2 {
+        Vec2::new(
+            px.x / self.grid_size - self.size.x / 2.0,
+            self.size.y / 2.0 - px.y / self.grid_size,
+        )
+    }
+}
+
+fn build_level(
+    project: &Project,
+    level: &Level,
+    levels_by_iid: &HashMap<&str, &Level>,
+    asset_path: &str,
+) -> Result<LevelAsset, bevy::asset::Error> {
+    let layers = level.layer_instances.as_ref().ok_or_else(|| {
+        error(format!(
+            "level {} has no layer instances, external levels are not supported",
+            level.identifier
+        ))
+    })?;
+
+    let space = LevelSpace {
+        grid_size: project.default_grid_size,
+        size: Vec2::new(level.px_wid, level.px_hei) / project.default_grid_size,
+    };
+
+    let mut asset = LevelAsset {
+        name: level.identifier.clone(),
+        bounds: Some(WorldBounds2D {
+            min: -space.size / 2.0,
+            max: space.size / 2.0,
+        }),
+        ..Default::default()
+    };
+
+    // layer instances are ordered top-most first
+    for layer in layers.iter().rev() {
+        match layer.layer_type.as_str() {
+            "IntGrid" => {
+                let values = project
+                    .defs
+                    .layers
+                    .iter()
+                    .find(|definition| definition.uid == layer.layer_def_uid)
+                    .map(|definition| {
+                        definition
+                            .int_grid_values
+                            .iter()
+                            .map(|value| (value.value, value))
+                            .collect::<HashMap<_, _>>()
+                    })
+                    .unwrap_or_default();
+
+                let cell_size = layer.grid_size / space.grid_size;
+
+                let mut used_values = layer.int_grid_csv.clone();
+                used_values.sort_unstable();
+                used_values.dedup();
+
+                for value in used_values.into_iter().filter(|value| *value != 0) {
+                    let solid = layer
+                        .int_grid_csv
+                        .iter()
+                        .map(|cell| *cell == value)
+                        .collect::<Vec<_>>();
+                    if solid.len() != layer.c_wid * layer.c_hei {
+                        return Err(error(format!(
+                            "layer {} has invalid IntGrid data",
+                            layer.identifier
+                        )));
+                    }
+
+                    let definition = values.get(&value);
+                    let color = definition
+                        .and_then(|definition| parse_hex_color(&definition.color))
+                        .unwrap_or_else(default_platform_color);
+                    let material = definition
+                        .and_then(|definition| definition.identifier.as_deref())
+                        .and_then(SurfaceMaterial::preset);
+
+                    for rect in merge_solid_tiles(layer.c_wid, layer.c_hei, &solid) {
+                        let top_left = space.to_world(Vec2::new(
+                            rect.x as f32 * layer.grid_size,
+                            rect.y as f32 * layer.grid_size,
+                        ));
+                        let size = Vec2::new(rect.width as f32, rect.height as f32) * cell_size;
+                        asset.platforms.push(PlatformDefinition {
+                            name: format!("{} {}", layer.identifier, value),
+                            position: top_left + Vec2::new(size.x, -size.y) / 2.0,
+                            size,
+                            color,
+                            collision_groups: LevelCollisionGroups::default(),
+                            material,
+                            ..Default::default()
+                        });
+                    }
+                }
+            }
+            "Entities" => {
+                for entity in layer.entity_instances.iter() {
+                    let size = Vec2::new(entity.width, entity.height);
+                    let top_left = Vec2::from(entity.px) - Vec2::from(entity.pivot) * size;
+                    let position = space.to_world(top_left + size / 2.0);
+
+                    let name = entity
+                        .field("name")
+                        .and_then(|name| name.as_str())
+                        .unwrap_or_default()
+                        .to_owned();
+
+                    match TriggerKind::from_class(&entity.identifier) {
+                        Some(kind) => asset.triggers.push(TriggerDefinition {
+                            name,
+                            kind,
+                            position,
+                            size: size / space.grid_size,
+                        }),
+                        None => asset.spawns.push(SpawnDefinition {
+                            prefab: entity.identifier.to_lowercase(),
+                            position,
+                            character: entity.character_overrides(),
+                        }),
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let center = |level: &Level| {
+        Vec2::new(
+            level.world_x + level.px_wid / 2.0,
+            -(level.world_y + level.px_hei / 2.0),
+        ) / project.default_grid_size
+    };
+
+    for neighbour in level.neighbours.iter() {
+        let direction = match neighbour.dir.as_str() {
+            "n" => NeighbourDirection::North,
+            "s" => NeighbourDirection::South,
+            "e" => NeighbourDirection::East,
+            "w" => NeighbourDirection::West,
+            _ => continue,
+        };
+
+        if let Some(other) = levels_by_iid.get(neighbour.level_iid.as_str()) {
+            asset.neighbours.push(LevelNeighbour {
+                level: format!("{}#{}", asset_path, other.identifier),
+                direction,
+                offset: center(other) - center(level),
+            });
+        }
+    }
+
+    Ok(asset)
+}
+
+/// Loads LDtk projects as levels
+#[derive(Default)]
+pub struct LdtkProjectLoader;
+
+impl AssetLoader for LdtkProjectLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let project: Project = serde_json::from_slice(bytes)?;
+            if project.external_levels {
+                return Err(error("LDtk external levels are not supported"));
+            }
+
+            let asset_path = load_context.path().to_string_lossy().replace('\\', "/");
+            let levels_by_iid = project
+                .levels
+                .iter()
+                .map(|level| (level.iid.as_str(), level))
+                .collect::<HashMap<_, _>>();
+
+            for (idx, level) in project.levels.iter().enumerate() {
+                let asset = build_level(&project, level, &levels_by_iid, &asset_path)?;
+                if idx == 0 {
+                    load_context.set_default_asset(LoadedAsset::new(asset.clone()));
+                }
+                load_context.set_labeled_asset(&level.identifier, LoadedAsset::new(asset));
+            }
+
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ldtk"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two 4x2 levels side by side, with an IntGrid layer at half the default grid size
+    const PROJECT: &str = r##"{
+  "defaultGridSize": 16,
+  "defs": {
+    "layers": [
+      {
+        "uid": 1,
+        "intGridValues": [{ "value": 1, "identifier": "ice", "color": "#88C0D0" }]
+      },
+      { "uid": 2 }
+    ]
+  },
+  "levels": [
+    {
+      "identifier": "Level_0",
+      "iid": "a",
+      "worldX": 0,
+      "worldY": 0,
+      "pxWid": 64,
+      "pxHei": 32,
+      "__neighbours": [{ "levelIid": "b", "dir": "e" }],
+      "layerInstances": [
+        {
+          "__identifier": "Entities",
+          "__type": "Entities",
+          "__cWid": 4,
+          "__cHei": 2,
+          "__gridSize": 16,
+          "layerDefUid": 2,
+          "entityInstances": [
+            {
+              "__identifier": "Player",
+              "__pivot": [0.5, 1],
+              "px": [24, 16],
+              "width": 16,
+              "height": 16,
+              "fieldInstances": [{ "__identifier": "speed", "__value": 5 }]
+            },
+            {
+              "__identifier": "Exit",
+              "__pivot": [0, 0],
+              "px": [56, 16],
+              "width": 8,
+              "height": 16,
+              "fieldInstances": [{ "__identifier": "name", "__value": "goal" }]
+            }
+          ]
+        },
+        {
+          "__identifier": "Collision",
+          "__type": "IntGrid",
+          "__cWid": 8,
+          "__cHei": 4,
+          "__gridSize": 8,
+          "layerDefUid": 1,
+          "intGridCsv": [
+            0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0,
+            0, 0, 0, 0, 0, 0, 0, 0,
+            1, 1, 1, 1, 1, 1, 1, 1
+          ]
+        }
+      ]
+    },
+    {
+      "identifier": "Level_1",
+      "iid": "b",
+      "worldX": 64,
+      "worldY": 0,
+      "pxWid": 64,
+      "pxHei": 32,
+      "layerInstances": null
+    }
+  ]
+}"##;
+
+    fn build(idx: usize) -> Result<LevelAsset, bevy::asset::Error> {
+        let project: Project = serde_json::from_str(PROJECT).unwrap();
+        let levels_by_iid = project
+            .levels
+            .iter()
+            .map(|level| (level.iid.as_str(), level))
+            .collect::<HashMap<_, _>>();
+
+        build_level(&project, &project.levels[idx], &levels_by_iid, "world.ldtk")
+    }
+
+    #[test]
+    fn level_bounds() {
+        let bounds = build(0).unwrap().bounds.unwrap();
+
+        assert_eq!(bounds.min, Vec2::new(-2.0, -1.0));
+        assert_eq!(bounds.max, Vec2::new(2.0, 1.0));
+    }
+
+    #[test]
+    fn int_grid_colliders() {
+        let level = build(0).unwrap();
+
+        // the bottom row of half size cells is merged into one collider
+        assert_eq!(level.platforms.len(), 1);
+        let platform = &level.platforms[0];
+        assert_eq!(platform.position, Vec2::new(0.0, -0.75));
+        assert_eq!(platform.size, Vec2::new(4.0, 0.5));
+        assert_eq!(platform.color, Color::rgb_u8(0x88, 0xc0, 0xd0));
+        assert!(platform.material.is_some());
+    }
+
+    #[test]
+    fn entities() {
+        let level = build(0).unwrap();
+
+        assert_eq!(level.spawns.len(), 1);
+        let spawn = &level.spawns[0];
+        assert_eq!(spawn.prefab, "player");
+        assert_eq!(spawn.position, Vec2::new(-0.5, 0.5));
+        assert_eq!(spawn.character.speed, Some(5.0));
+        assert_eq!(spawn.character.jump_force, None);
+
+        assert_eq!(level.triggers.len(), 1);
+        let trigger = &level.triggers[0];
+        assert_eq!(trigger.name, "goal");
+        assert_eq!(trigger.kind, TriggerKind::Exit);
+        assert_eq!(trigger.position, Vec2::new(1.75, -0.5));
+        assert_eq!(trigger.size, Vec2::new(0.5, 1.0));
+    }
+
+    #[test]
+    fn neighbours() {
+        let level = build(0).unwrap();
+
+        assert_eq!(level.neighbours.len(), 1);
+        let neighbour = &level.neighbours[0];
+        assert_eq!(neighbour.level, "world.ldtk#Level_1");
+        assert_eq!(neighbour.direction, NeighbourDirection::East);
+        assert_eq!(neighbour.offset, Vec2::new(4.0, 0.0));
+    }
+
+    #[test]
+    fn missing_layer_instances() {
+        assert!(build(1).is_err());
+    }
+}
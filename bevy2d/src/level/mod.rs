@@ -0,0 +1,593 @@
+//! Level assets
+
+pub mod generator;
+pub mod ldtk;
+pub mod tiled;
+
+use bevy::asset::{AssetLoader, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use core_lib::components::world::SurfaceMaterial;
+
+use crate::resources::world::WorldBounds2D;
+use crate::{CHARACTER_LAYER, WORLD_LAYER};
+
+/// Level collider collision groups
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct LevelCollisionGroups {
+    /// The physics layers the collider is a member of
+    pub memberships: u32,
+
+    /// The physics layers the collider interacts with
+    pub filters: u32,
+}
+
+impl Default for LevelCollisionGroups {
+    fn default() -> Self {
+        Self {
+            memberships: WORLD_LAYER,
+            filters: CHARACTER_LAYER,
+        }
+    }
+}
+
+impl From<LevelCollisionGroups> for CollisionGroups {
+    fn from(groups: LevelCollisionGroups) -> Self {
+        CollisionGroups::new(groups.memberships, groups.filters)
+    }
+}
+
+fn default_platform_name() -> String {
+    "Platform".to_owned()
+}
+
+fn default_platform_color() -> Color {
+    Color::WHITE
+}
+
+/// Parses an #AARRGGBB or #RRGGBB hex color
+fn parse_hex_color(value: &str) -> Option<Color> {
+    let hex = value.trim_start_matches('#');
+    let (alpha, rgb) = match hex.len() {
+        8 => (&hex[0..2], &hex[2..8]),
+        6 => ("ff", hex),
+        _ => return None,
+    };
+
+    let component = |c: &str| u8::from_str_radix(c, 16).ok();
+    Some(Color::rgba_u8(
+        component(&rgb[0..2])?,
+        component(&rgb[2..4])?,
+        component(&rgb[4..6])?,
+        component(alpha)?,
+    ))
+}
+
+/// How a moving platform follows its waypoints
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum PathMode {
+    /// Stop at the last waypoint
+    Once,
+
+    /// Reverse at each end
+    PingPong,
+
+    /// Go back to the start after the last waypoint
+    Loop,
+}
+
+impl Default for PathMode {
+    fn default() -> Self {
+        Self::PingPong
+    }
+}
+
+/// How a moving platform moves between waypoints
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum PathEasing {
+    /// Constant speed
+    Linear,
+
+    /// Speed up leaving a waypoint and slow down arriving at the next
+    EaseInOut,
+}
+
+impl Default for PathEasing {
+    fn default() -> Self {
+        Self::Linear
+    }
+}
+
+impl PathEasing {
+    /// Eases segment progress (0 - 1)
+    pub fn ease(&self, t: f32) -> f32 {
+        match self {
+            Self::Linear => t,
+            Self::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// A moving platform path
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PlatformPath {
+    /// Waypoints, relative to the platform position
+    ///
+    /// The platform position is always the first waypoint
+    pub waypoints: Vec<Vec2>,
+
+    /// How fast the platform moves
+    pub speed: f32,
+
+    /// How the platform follows the waypoints
+    #[serde(default)]
+    pub mode: PathMode,
+
+    /// How the platform moves between waypoints
+    #[serde(default)]
+    pub easing: PathEasing,
+}
+
+/// A platform
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PlatformDefinition {
+    /// The platform entity name
+    #[serde(default = "default_platform_name")]
+    pub name: String,
+
+    /// The center of the platform
+    pub position: Vec2,
+
+    /// The full size of the platform
+    pub size: Vec2,
+
+    /// The platform rotation, for slopes (degrees)
+    #[serde(default)]
+    pub rotation: f32,
+
+    /// The platform sprite color
+    #[serde(default = "default_platform_color")]
+    pub color: Color,
+
+    /// The platform collision groups
+    #[serde(default)]
+    pub collision_groups: LevelCollisionGroups,
+
+    /// Can characters pass through the platform from below?
+    #[serde(default)]
+    pub one_way: bool,
+
+    /// Moving platform path, static platforms don't have one
+    #[serde(default)]
+    pub path: Option<PlatformPath>,
+
+    /// The platform surface material
+    #[serde(default)]
+    pub material: Option<SurfaceMaterial>,
+}
+
+impl Default for PlatformDefinition {
+    fn default() -> Self {
+        Self {
+            name: default_platform_name(),
+            position: Vec2::ZERO,
+            size: Vec2::ONE,
+            rotation: 0.0,
+            color: default_platform_color(),
+            collision_groups: LevelCollisionGroups::default(),
+            one_way: false,
+            path: None,
+            material: None,
+        }
+    }
+}
+
+/// Per-spawn character tuning overrides
+#[derive(Debug, Default, Copy, Clone, Deserialize, Serialize)]
+pub struct CharacterOverrides {
+    /// Overrides the character speed
+    #[serde(default)]
+    pub speed: Option<f32>,
+
+    /// Overrides the character air control factor
+    #[serde(default)]
+    pub air_control_factor: Option<f32>,
+
+    /// Overrides the character jump force
+    #[serde(default)]
+    pub jump_force: Option<Vec2>,
+}
+
+/// A spawn point
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SpawnDefinition {
+    /// The prefab to spawn
+    pub prefab: String,
+
+    /// Where to spawn it
+    pub position: Vec2,
+
+    /// Character tuning overrides
+    #[serde(default)]
+    pub character: CharacterOverrides,
+}
+
+/// What a trigger volume does
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum TriggerKind {
+    /// Nothing built-in, just a named volume
+    Generic,
+
+    /// Completes the level
+    Exit,
+
+    /// Can be climbed
+    Ladder,
+
+    /// Can be swum in
+    Water,
+
+    /// Hurts characters that touch it
+    Hazard,
+}
+
+impl Default for TriggerKind {
+    fn default() -> Self {
+        Self::Generic
+    }
+}
+
+impl TriggerKind {
+    /// Returns the trigger kind for an imported object class (case insensitive)
+    ///
+    /// Returns None if the class isn't a trigger class
+    pub fn from_class(class: &str) -> Option<Self> {
+        match class.to_lowercase().as_str() {
+            "trigger" => Some(Self::Generic),
+            "exit" => Some(Self::Exit),
+            "ladder" => Some(Self::Ladder),
+            "water" => Some(Self::Water),
+            "hazard" => Some(Self::Hazard),
+            _ => None,
+        }
+    }
+}
+
+/// A trigger volume
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TriggerDefinition {
+    /// The trigger name
+    pub name: String,
+
+    /// What the trigger does
+    #[serde(default)]
+    pub kind: TriggerKind,
+
+    /// The center of the trigger
+    pub position: Vec2,
+
+    /// The full size of the trigger
+    pub size: Vec2,
+}
+
+/// The side of a level a neighbour is on
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum NeighbourDirection {
+    North,
+    South,
+    East,
+    West,
+}
+
+impl NeighbourDirection {
+    /// Returns the side of a level a position relative to the level center is closest to
+    pub fn from_offset(offset: Vec2) -> Self {
+        if offset.x.abs() >= offset.y.abs() {
+            if offset.x >= 0.0 {
+                Self::East
+            } else {
+                Self::West
+            }
+        } else if offset.y >= 0.0 {
+            Self::North
+        } else {
+            Self::South
+        }
+    }
+}
+
+/// A neighbouring level
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct LevelNeighbour {
+    /// The neighbouring level asset path
+    pub level: String,
+
+    /// Which side of this level the neighbour is on
+    pub direction: NeighbourDirection,
+
+    /// The neighbour world position relative to this level
+    #[serde(default)]
+    pub offset: Vec2,
+}
+
+/// A single tile in a tile chunk
+#[derive(Debug, Copy, Clone)]
+pub struct ChunkTile {
+    /// The tile position relative to the chunk
+    pub position: Vec2,
+
+    /// The tile index in the chunk texture atlas
+    pub index: usize,
+}
+
+/// A chunk of tile sprites that share a texture atlas
+#[derive(Debug, Clone)]
+pub struct TileChunk {
+    /// The chunk texture atlas
+    pub atlas: Handle<TextureAtlas>,
+
+    /// The chunk position
+    pub position: Vec3,
+
+    /// The size of each tile
+    pub tile_size: Vec2,
+
+    /// The chunk tiles
+    pub tiles: Vec<ChunkTile>,
+}
+
+/// A rectangle of tiles in tile coordinates
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TileRect {
+    /// The left-most tile column
+    pub x: usize,
+
+    /// The top-most tile row
+    pub y: usize,
+
+    /// The number of tile columns
+    pub width: usize,
+
+    /// The number of tile rows
+    pub height: usize,
+}
+
+/// Greedily merges solid tiles into as few rectangles as possible
+///
+/// Rectangles are grown right as far as possible and then down
+/// as far as the full row stays solid. `solid` is row-major
+pub fn merge_solid_tiles(width: usize, height: usize, solid: &[bool]) -> Vec<TileRect> {
+    let mut merged = vec![false; width * height];
+    let mut rects = vec![];
+
+    let available = |merged: &[bool], x: usize, y: usize| {
+        let idx = y * width + x;
+        solid[idx] && !merged[idx]
+    };
+
+    for y in 0..height {
+        let mut x = 0;
+        while x < width {
+            if !available(&merged, x, y) {
+                x += 1;
+                continue;
+            }
+
+            let mut rect_width = 1;
+            while x + rect_width < width && available(&merged, x + rect_width, y) {
+                rect_width += 1;
+            }
+
+            let mut rect_height = 1;
+            while y + rect_height < height
+                && (x..x + rect_width).all(|rx| available(&merged, rx, y + rect_height))
+            {
+                rect_height += 1;
+            }
+
+            for ry in y..y + rect_height {
+                for rx in x..x + rect_width {
+                    merged[ry * width + rx] = true;
+                }
+            }
+
+            rects.push(TileRect {
+                x,
+                y,
+                width: rect_width,
+                height: rect_height,
+            });
+
+            x += rect_width;
+        }
+    }
+
+    rects
+}
+
+/// A level
+#[derive(Debug, Default, Clone, Deserialize, Serialize, TypeUuid)]
+#[uuid = "5e6c6311-2ccc-4219-99e7-813dcd43d2cc"]
+pub struct LevelAsset {
+    /// The level name
+    pub name: String,
+
+    /// The level world bounds
+    ///
+    /// If this isn't set the bounds are derived from the window
+    #[serde(default)]
+    pub bounds: Option<WorldBounds2D>,
+
+    /// The level platforms
+    #[serde(default)]
+    pub platforms: Vec<PlatformDefinition>,
+
+    /// The level spawn points
+    #[serde(default)]
+    pub spawns: Vec<SpawnDefinition>,
+
+    /// The level trigger volumes
+    #[serde(default)]
+    pub triggers: Vec<TriggerDefinition>,
+
+    /// Neighbouring levels, exits lead to the neighbour on their side of the level
+    #[serde(default)]
+    pub neighbours: Vec<LevelNeighbour>,
+
+    /// Imported tile sprites
+    #[serde(skip)]
+    pub tile_chunks: Vec<TileChunk>,
+}
+
+/// Loads RON level files
+#[derive(Default)]
+pub struct LevelAssetLoader;
+
+impl AssetLoader for LevelAssetLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let level = ron::de::from_bytes::<LevelAsset>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(level));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(rows: &[&str]) -> Vec<bool> {
+        rows.iter()
+            .flat_map(|row| row.chars().map(|tile| tile == '#'))
+            .collect()
+    }
+
+    fn rect(x: usize, y: usize, width: usize, height: usize) -> TileRect {
+        TileRect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn merge_empty_tiles() {
+        assert!(merge_solid_tiles(3, 2, &solid(&["...", "..."])).is_empty());
+    }
+
+    #[test]
+    fn merge_full_tiles() {
+        assert_eq!(
+            merge_solid_tiles(3, 2, &solid(&["###", "###"])),
+            vec![rect(0, 0, 3, 2)]
+        );
+    }
+
+    #[test]
+    fn merge_grows_right_then_down() {
+        assert_eq!(
+            merge_solid_tiles(4, 3, &solid(&["###.", "#...", "#..#"])),
+            vec![rect(0, 0, 3, 1), rect(0, 1, 1, 2), rect(3, 2, 1, 1)]
+        );
+    }
+
+    #[test]
+    fn merge_covers_every_solid_tile_once() {
+        let rows = ["#.##.#", "######", ".##..#", "###.##"];
+        let width = 6;
+        let solid = solid(&rows);
+
+        let mut covered = vec![0; solid.len()];
+        for rect in merge_solid_tiles(width, rows.len(), &solid) {
+            for y in rect.y..rect.y + rect.height {
+                for x in rect.x..rect.x + rect.width {
+                    covered[y * width + x] += 1;
+                }
+            }
+        }
+
+        for (solid, covered) in solid.iter().zip(covered) {
+            assert_eq!(covered, usize::from(*solid));
+        }
+    }
+
+    #[test]
+    fn neighbour_direction_from_offset() {
+        assert_eq!(
+            NeighbourDirection::from_offset(Vec2::new(10.0, -2.0)),
+            NeighbourDirection::East
+        );
+        assert_eq!(
+            NeighbourDirection::from_offset(Vec2::new(-10.0, 2.0)),
+            NeighbourDirection::West
+        );
+        assert_eq!(
+            NeighbourDirection::from_offset(Vec2::new(1.0, 5.0)),
+            NeighbourDirection::North
+        );
+        assert_eq!(
+            NeighbourDirection::from_offset(Vec2::new(-1.0, -5.0)),
+            NeighbourDirection::South
+        );
+    }
+
+    #[test]
+    fn parse_shipped_levels() {
+        for source in [
+            include_str!("../../assets/levels/arena.level.ron"),
+            include_str!("../../assets/levels/tower.level.ron"),
+        ] {
+            let level = ron::de::from_str::<LevelAsset>(source).unwrap();
+
+            assert!(!level.name.is_empty());
+            assert!(!level.platforms.is_empty());
+            assert!(!level.spawns.is_empty());
+        }
+    }
+
+    #[test]
+    fn parse_level_defaults() {
+        let level = ron::de::from_str::<LevelAsset>(
+            r#"(
+                name: "Test",
+                platforms: [
+                    (position: (1.0, 2.0), size: (3.0, 1.0)),
+                ],
+            )"#,
+        )
+        .unwrap();
+
+        assert_eq!(level.name, "Test");
+        assert!(level.bounds.is_none());
+        assert!(level.spawns.is_empty());
+        assert!(level.triggers.is_empty());
+
+        let platform = &level.platforms[0];
+        assert_eq!(platform.name, "Platform");
+        assert_eq!(platform.position, Vec2::new(1.0, 2.0));
+        assert_eq!(platform.size, Vec2::new(3.0, 1.0));
+        assert_eq!(platform.color, Color::WHITE);
+        assert_eq!(platform.collision_groups, LevelCollisionGroups::default());
+        assert!(!platform.one_way);
+        assert!(platform.path.is_none());
+    }
+
+    #[test]
+    fn parse_level_missing_name() {
+        assert!(ron::de::from_str::<LevelAsset>("(platforms: [])").is_err());
+    }
+}
@@ -0,0 +1,859 @@
+//! Tiled map import
+//!
+//! Supports orthogonal, finite maps with embedded tilesets in either
+//! the XML (.tmx, CSV encoded layers) or the JSON (.tmj) format.
+//! Group layers are flattened into their child layers
+//!
+//! Tile layers with a `collision` bool property become merged static colliders.
+//! Objects are classified by their class (or type):
+//!   * `spawn` - a spawn point, the object name is the prefab to spawn
+//!   * `platform` - a static platform, optionally colored with a `color` property
+//!     and made one-way with a `one_way` bool property.
+//!     A `material` property sets the surface material preset
+//!     (`ice`, `mud`, `bouncy` or `conveyor`), conveyor speed comes from
+//!     a `conveyor_speed` float property
+//!   * `trigger` - a trigger volume
+//!   * `exit` - a trigger volume that completes the level
+//!   * `ladder` - a climbable trigger volume
+//!   * `water` - a trigger volume that characters swim in
+//!   * `hazard` - a trigger volume that hurts characters
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use bevy::asset::{AssetLoader, AssetPath, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::utils::BoxedFuture;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use serde::Deserialize;
+
+use core_lib::components::world::SurfaceMaterial;
+
+use super::*;
+use crate::resources::world::WorldBounds2D;
+
+/// Tile layers are split into square chunks of this many tiles
+const CHUNK_SIZE: usize = 16;
+
+/// Tile gids store flip flags in the high bits
+const TILE_GID_MASK: u32 = 0x1fffffff;
+
+#[derive(Debug, Default)]
+struct TiledTileset {
+    first_gid: u32,
+    image: String,
+    tile_width: f32,
+    tile_height: f32,
+    spacing: f32,
+    columns: usize,
+    tile_count: usize,
+}
+
+#[derive(Debug, Default)]
+struct TiledObject {
+    name: String,
+    class: String,
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+    properties: HashMap<String, String>,
+}
+
+#[derive(Debug)]
+enum TiledLayer {
+    Tiles {
+        name: String,
+        visible: bool,
+        data: Vec<u32>,
+        properties: HashMap<String, String>,
+    },
+    Objects {
+        objects: Vec<TiledObject>,
+    },
+}
+
+#[derive(Debug, Default)]
+struct TiledMap {
+    width: usize,
+    height: usize,
+    tile_width: f32,
+    tile_height: f32,
+    tilesets: Vec<TiledTileset>,
+    layers: Vec<TiledLayer>,
+}
+
+fn error(message: impl Into<String>) -> bevy::asset::Error {
+    bevy::asset::Error::msg(message.into())
+}
+
+fn parse_bool(value: Option<&String>) -> bool {
+    value.map(|value| value == "true").unwrap_or_default()
+}
+
+fn parse_material(properties: &HashMap<String, String>) -> Option<SurfaceMaterial> {
+    let name = properties.get("material")?;
+    let mut material = match SurfaceMaterial::preset(name) {
+        Some(material) => material,
+        None => {
+            warn!("ignoring unknown Tiled surface material '{}'", name);
+            return None;
+        }
+    };
+
+    if let Some(speed) = properties
+        .get("conveyor_speed")
+        .and_then(|speed| speed.parse().ok())
+    {
+        material.conveyor_speed = speed;
+    }
+
+    Some(material)
+}
+
+// JSON (.tmj) format
+
+#[derive(Debug, Deserialize)]
+struct JsonProperty {
+    name: String,
+    value: serde_json::Value,
+}
+
+fn json_properties(properties: Vec<JsonProperty>) -> HashMap<String, String> {
+    properties
+        .into_iter()
+        .map(|property| {
+            let value = match property.value {
+                serde_json::Value::String(value) => value,
+                value => value.to_string(),
+            };
+            (property.name, value)
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonTileset {
+    firstgid: u32,
+    image: Option<String>,
+    source: Option<String>,
+    tilewidth: f32,
+    tileheight: f32,
+    #[serde(default)]
+    spacing: f32,
+    columns: usize,
+    tilecount: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonObject {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    class: String,
+    #[serde(default, rename = "type")]
+    object_type: String,
+    x: f32,
+    y: f32,
+    #[serde(default)]
+    width: f32,
+    #[serde(default)]
+    height: f32,
+    #[serde(default)]
+    properties: Vec<JsonProperty>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type")]
+enum JsonLayer {
+    #[serde(rename = "tilelayer")]
+    Tiles {
+        name: String,
+        #[serde(default = "default_visible")]
+        visible: bool,
+        #[serde(default)]
+        data: Vec<u32>,
+        #[serde(default)]
+        properties: Vec<JsonProperty>,
+    },
+    #[serde(rename = "objectgroup")]
+    Objects {
+        #[serde(default)]
+        objects: Vec<JsonObject>,
+    },
+    #[serde(rename = "group")]
+    Group {
+        #[serde(default)]
+        layers: Vec<JsonLayer>,
+    },
+    #[serde(other)]
+    Unsupported,
+}
+
+fn default_visible() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonMap {
+    width: usize,
+    height: usize,
+    tilewidth: f32,
+    tileheight: f32,
+    #[serde(default)]
+    infinite: bool,
+    #[serde(default)]
+    tilesets: Vec<JsonTileset>,
+    #[serde(default)]
+    layers: Vec<JsonLayer>,
+}
+
+fn parse_tmj(bytes: &[u8]) -> Result<TiledMap, bevy::asset::Error> {
+    let map: JsonMap = serde_json::from_slice(bytes)?;
+    if map.infinite {
+        return Err(error("infinite Tiled maps are not supported"));
+    }
+
+    let mut tilesets = vec![];
+    for tileset in map.tilesets {
+        if let Some(source) = tileset.source {
+            return Err(error(format!(
+                "external tileset {} is not supported",
+                source
+            )));
+        }
+
+        tilesets.push(TiledTileset {
+            first_gid: tileset.firstgid,
+            image: tileset
+                .image
+                .ok_or_else(|| error("image collection tilesets are not supported"))?,
+            tile_width: tileset.tilewidth,
+            tile_height: tileset.tileheight,
+            spacing: tileset.spacing,
+            columns: tileset.columns,
+            tile_count: tileset.tilecount,
+        });
+    }
+
+    let mut layers = vec![];
+    json_layers(map.layers, &mut layers);
+
+    Ok(TiledMap {
+        width: map.width,
+        height: map.height,
+        tile_width: map.tilewidth,
+        tile_height: map.tileheight,
+        tilesets,
+        layers,
+    })
+}
+
+/// Converts JSON layers, flattening group layers into their children
+fn json_layers(source: Vec<JsonLayer>, layers: &mut Vec<TiledLayer>) {
+    for layer in source {
+        match layer {
+            JsonLayer::Tiles {
+                name,
+                visible,
+                data,
+                properties,
+            } => layers.push(TiledLayer::Tiles {
+                name,
+                visible,
+                data,
+                properties: json_properties(properties),
+            }),
+            JsonLayer::Objects { objects } => layers.push(TiledLayer::Objects {
+                objects: objects
+                    .into_iter()
+                    .map(|object| TiledObject {
+                        name: object.name,
+                        class: if object.class.is_empty() {
+                            object.object_type
+                        } else {
+                            object.class
+                        },
+                        x: object.x,
+                        y: object.y,
+                        width: object.width,
+                        height: object.height,
+                        properties: json_properties(object.properties),
+                    })
+                    .collect(),
+            }),
+            JsonLayer::Group { layers: children } => json_layers(children, layers),
+            JsonLayer::Unsupported => (),
+        }
+    }
+}
+
+// XML (.tmx) format
+
+fn xml_attributes(
+    reader: &Reader<&[u8]>,
+    element: &BytesStart,
+) -> Result<HashMap<String, String>, bevy::asset::Error> {
+    let mut attributes = HashMap::new();
+    for attribute in element.attributes() {
+        let attribute = attribute?;
+        attributes.insert(
+            String::from_utf8_lossy(attribute.key).into_owned(),
+            attribute.unescape_and_decode_value(reader)?,
+        );
+    }
+    Ok(attributes)
+}
+
+fn parse_attribute<T: std::str::FromStr + Default>(
+    attributes: &HashMap<String, String>,
+    name: &str,
+) -> T {
+    attributes
+        .get(name)
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_default()
+}
+
+fn parse_tmx(bytes: &[u8]) -> Result<TiledMap, bevy::asset::Error> {
+    let mut reader = Reader::from_reader(bytes);
+    reader.trim_text(true);
+
+    let mut map = TiledMap::default();
+
+    // per-tile images and collision shapes inside tilesets are not level content
+    let mut in_tileset = false;
+    let mut in_tile = false;
+
+    // properties belong to whatever was opened most recently
+    let mut in_layer = false;
+    let mut in_object = false;
+    let mut csv_data = false;
+
+    let mut buf = vec![];
+    loop {
+        let event = reader.read_event(&mut buf)?;
+        match &event {
+            Event::Start(element) | Event::Empty(element) => {
+                let empty = matches!(event, Event::Empty(_));
+                let attributes = xml_attributes(&reader, element)?;
+
+                match element.name() {
+                    b"map" => {
+                        if parse_attribute::<u32>(&attributes, "infinite") != 0 {
+                            return Err(error("infinite Tiled maps are not supported"));
+                        }
+                        map.width = parse_attribute(&attributes, "width");
+                        map.height = parse_attribute(&attributes, "height");
+                        map.tile_width = parse_attribute(&attributes, "tilewidth");
+                        map.tile_height = parse_attribute(&attributes, "tileheight");
+                    }
+                    b"tileset" => {
+                        if let Some(source) = attributes.get("source") {
+                            return Err(error(format!(
+                                "external tileset {} is not supported",
+                                source
+                            )));
+                        }
+                        map.tilesets.push(TiledTileset {
+                            first_gid: parse_attribute(&attributes, "firstgid"),
+                            tile_width: parse_attribute(&attributes, "tilewidth"),
+                            tile_height: parse_attribute(&attributes, "tileheight"),
+                            spacing: parse_attribute(&attributes, "spacing"),
+                            columns: parse_attribute(&attributes, "columns"),
+                            tile_count: parse_attribute(&attributes, "tilecount"),
+                            ..Default::default()
+                        });
+                        in_tileset = !empty;
+                    }
+                    b"tile" if in_tileset => in_tile = !empty,
+                    b"image" if !in_tile => {
+                        if let Some(tileset) = map.tilesets.last_mut() {
+                            tileset.image = attributes.get("source").cloned().unwrap_or_default();
+                        }
+                    }
+                    b"layer" => {
+                        map.layers.push(TiledLayer::Tiles {
+                            name: attributes.get("name").cloned().unwrap_or_default(),
+                            visible: attributes
+                                .get("visible")
+                                .map(|visible| visible != "0")
+                                .unwrap_or(true),
+                            data: vec![],
+                            properties: HashMap::new(),
+                        });
+                        in_layer = !empty;
+                    }
+                    b"data" => {
+                        if attributes.get("encoding").map(String::as_str) != Some("csv") {
+                            return Err(error("only CSV encoded tile layers are supported"));
+                        }
+                        csv_data = !empty;
+                    }
+                    b"objectgroup" if !in_tileset => {
+                        map.layers.push(TiledLayer::Objects { objects: vec![] });
+                    }
+                    b"object" if !in_tileset => {
+                        if let Some(TiledLayer::Objects { objects }) = map.layers.last_mut() {
+                            let class = attributes
+                                .get("class")
+                                .or_else(|| attributes.get("type"))
+                                .cloned()
+                                .unwrap_or_default();
+                            objects.push(TiledObject {
+                                name: attributes.get("name").cloned().unwrap_or_default(),
+                                class,
+                                x: parse_attribute(&attributes, "x"),
+                                y: parse_attribute(&attributes, "y"),
+                                width: parse_attribute(&attributes, "width"),
+                                height: parse_attribute(&attributes, "height"),
+                                properties: HashMap::new(),
+                            });
+                        }
+                        in_object = !empty;
+                    }
+                    b"property" => {
+                        let name = attributes.get("name").cloned().unwrap_or_default();
+                        let value = attributes.get("value").cloned().unwrap_or_default();
+
+                        match map.layers.last_mut() {
+                            Some(TiledLayer::Objects { objects }) if in_object => {
+                                if let Some(object) = objects.last_mut() {
+                                    object.properties.insert(name, value);
+                                }
+                            }
+                            Some(TiledLayer::Tiles { properties, .. }) if in_layer => {
+                                properties.insert(name, value);
+                            }
+                            _ => (),
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            Event::Text(text) if csv_data => {
+                let text = text.unescape_and_decode(&reader)?;
+                if let Some(TiledLayer::Tiles { data, .. }) = map.layers.last_mut() {
+                    for gid in text.split(',').map(str::trim).filter(|gid| !gid.is_empty()) {
+                        data.push(gid.parse()?);
+                    }
+                }
+            }
+            Event::End(element) => match element.name() {
+                b"tileset" => in_tileset = false,
+                b"tile" => in_tile = false,
+                b"layer" => in_layer = false,
+                b"data" => csv_data = false,
+                b"object" => in_object = false,
+                _ => (),
+            },
+            Event::Eof => break,
+            _ => (),
+        }
+        buf.clear();
+    }
+
+    Ok(map)
+}
+
+// level conversion
+
+impl TiledMap {
+    /// Converts a tile column / row to the world position of the tile center
+    fn tile_center(&self, x: f32, y: f32) -> Vec2 {
+        Vec2::new(
+            x + 0.5 - self.width as f32 / 2.0,
+            self.height as f32 / 2.0 - y - 0.5,
+        )
+    }
+
+    /// Converts a map pixel position to a world position
+    fn pixel_to_world(&self, x: f32, y: f32) -> Vec2 {
+        Vec2::new(
+            x / self.tile_width - self.width as f32 / 2.0,
+            self.height as f32 / 2.0 - y / self.tile_height,
+        )
+    }
+
+    fn tileset_for(&self, gid: u32) -> Option<usize> {
+        self.tilesets
+            .iter()
+            .rposition(|tileset| tileset.first_gid <= gid)
+    }
+}
+
+fn build_level(
+    map: TiledMap,
+    load_context: &mut LoadContext,
+) -> Result<LevelAsset, bevy::asset::Error> {
+    if map.width == 0 || map.height == 0 || map.tile_width <= 0.0 || map.tile_height <= 0.0 {
+        return Err(error("invalid Tiled map dimensions"));
+    }
+
+    let mut level = LevelAsset {
+        name: load_context
+            .path()
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        bounds: Some(WorldBounds2D {
+            min: Vec2::new(-(map.width as f32) / 2.0, -(map.height as f32) / 2.0),
+            max: Vec2::new(map.width as f32 / 2.0, map.height as f32 / 2.0),
+        }),
+        ..Default::default()
+    };
+
+    // tilesets become texture atlases
+    let map_directory = load_context
+        .path()
+        .parent()
+        .unwrap_or_else(|| Path::new(""))
+        .to_path_buf();
+
+    let mut atlases = vec![];
+    for (idx, tileset) in map.tilesets.iter().enumerate() {
+        let image_path = AssetPath::new(map_directory.join(&tileset.image), None);
+        let image: Handle<Image> = load_context.get_handle(image_path.clone());
+
+        let columns = tileset.columns.max(1);
+        let rows = (tileset.tile_count + columns - 1) / columns;
+        let atlas = TextureAtlas::from_grid_with_padding(
+            image,
+            Vec2::new(tileset.tile_width, tileset.tile_height),
+            columns,
+            rows,
+            Vec2::splat(tileset.spacing),
+        );
+
+        atlases.push(load_context.set_labeled_asset(
+            &format!("tileset{}", idx),
+            LoadedAsset::new(atlas).with_dependency(image_path),
+        ));
+    }
+
+    for (layer_idx, layer) in map.layers.iter().enumerate() {
+        match layer {
+            TiledLayer::Tiles {
+                name,
+                visible,
+                data,
+                properties,
+            } => {
+                if data.len() != map.width * map.height {
+                    return Err(error(format!("tile layer {} has invalid data", name)));
+                }
+
+                if *visible {
+                    build_chunks(&map, &atlases, layer_idx, data, &mut level);
+                }
+
+                if parse_bool(properties.get("collision")) {
+                    let solid = data
+                        .iter()
+                        .map(|gid| gid & TILE_GID_MASK != 0)
+                        .collect::<Vec<_>>();
+
+                    for rect in merge_solid_tiles(map.width, map.height, &solid) {
+                        let top_left = map.tile_center(rect.x as f32, rect.y as f32);
+                        let size = Vec2::new(rect.width as f32, rect.height as f32);
+                        level.platforms.push(PlatformDefinition {
+                            name: format!("{} Collider", name),
+                            position: top_left + Vec2::new(size.x - 1.0, 1.0 - size.y) / 2.0,
+                            size,
+                            color: Color::NONE,
+                            collision_groups: LevelCollisionGroups::default(),
+                            ..Default::default()
+                        });
+                    }
+                }
+            }
+            TiledLayer::Objects { objects } => {
+                for object in objects {
+                    let size = Vec2::new(
+                        object.width / map.tile_width,
+                        object.height / map.tile_height,
+                    );
+                    let position =
+                        map.pixel_to_world(object.x, object.y) + Vec2::new(size.x, -size.y) / 2.0;
+
+                    match object.class.as_str() {
+                        "spawn" => level.spawns.push(SpawnDefinition {
+                            prefab: object.name.clone(),
+                            position,
+                            character: CharacterOverrides::default(),
+                        }),
+                        "platform" => level.platforms.push(PlatformDefinition {
+                            name: if object.name.is_empty() {
+                                default_platform_name()
+                            } else {
+                                object.name.clone()
+                            },
+                            position,
+                            size,
+                            color: object
+                                .properties
+                                .get("color")
+                                .and_then(|color| parse_hex_color(color))
+                                .unwrap_or_else(default_platform_color),
+                            collision_groups: LevelCollisionGroups::default(),
+                            one_way: parse_bool(object.properties.get("one_way")),
+                            material: parse_material(&object.properties),
+                            ..Default::default()
+                        }),
+                        class => match TriggerKind::from_class(class) {
+                            Some(kind) => level.triggers.push(TriggerDefinition {
+                                name: object.name.clone(),
+                                kind,
+                                position,
+                                size,
+                            }),
+                            None => {
+                                warn!("ignoring Tiled object with unknown class '{}'", class)
+                            }
+                        },
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(level)
+}
+
+fn build_chunks(
+    map: &TiledMap,
+    atlases: &[Handle<TextureAtlas>],
+    layer_idx: usize,
+    data: &[u32],
+    level: &mut LevelAsset,
+) {
+    // tiles sit behind everything else, later layers on top
+    let z = -1.0 + layer_idx as f32 * 0.01;
+
+    for chunk_y in (0..map.height).step_by(CHUNK_SIZE) {
+        for chunk_x in (0..map.width).step_by(CHUNK_SIZE) {
+            let origin = map.tile_center(chunk_x as f32, chunk_y as f32);
+
+            // one chunk per tileset used in this part of the map
+            let mut chunks: HashMap<usize, Vec<ChunkTile>> = HashMap::new();
+            for y in chunk_y..(chunk_y + CHUNK_SIZE).min(map.height) {
+                for x in chunk_x..(chunk_x + CHUNK_SIZE).min(map.width) {
+                    let gid = data[y * map.width + x] & TILE_GID_MASK;
+                    if gid == 0 {
+                        continue;
+                    }
+
+                    let tileset_idx = match map.tileset_for(gid) {
+                        Some(tileset_idx) => tileset_idx,
+                        None => continue,
+                    };
+
+                    chunks.entry(tileset_idx).or_default().push(ChunkTile {
+                        position: map.tile_center(x as f32, y as f32) - origin,
+                        index: (gid - map.tilesets[tileset_idx].first_gid) as usize,
+                    });
+                }
+            }
+
+            for (tileset_idx, tiles) in chunks {
+                level.tile_chunks.push(TileChunk {
+                    atlas: atlases[tileset_idx].clone(),
+                    position: origin.extend(z),
+                    tile_size: Vec2::ONE,
+                    tiles,
+                });
+            }
+        }
+    }
+}
+
+/// Loads Tiled maps as levels
+#[derive(Default)]
+pub struct TiledMapLoader;
+
+impl AssetLoader for TiledMapLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let map = match load_context.path().extension().and_then(|ext| ext.to_str()) {
+                Some("tmj") => parse_tmj(bytes)?,
+                _ => parse_tmx(bytes)?,
+            };
+
+            let level = build_level(map, load_context)?;
+            load_context.set_default_asset(LoadedAsset::new(level));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["tmx", "tmj"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TMX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<map version="1.9" orientation="orthogonal" width="4" height="2" tilewidth="16" tileheight="16" infinite="0">
+ <tileset firstgid="1" name="tiles" tilewidth="16" tileheight="16" spacing="1" tilecount="4" columns="2">
+  <image source="tiles.png" width="33" height="33"/>
+  <tile id="0">
+   <image source="tile.png" width="16" height="16"/>
+   <objectgroup draworder="index">
+    <object id="1" x="0" y="0" width="16" height="16"/>
+   </objectgroup>
+  </tile>
+ </tileset>
+ <layer id="1" name="Ground" width="4" height="2">
+  <properties>
+   <property name="collision" type="bool" value="true"/>
+  </properties>
+  <data encoding="csv">
+0,0,0,0,
+1,2,2,1
+</data>
+ </layer>
+ <group id="2" name="Group">
+  <objectgroup id="3" name="Objects">
+   <object id="2" name="player" class="spawn" x="16" y="0" width="16" height="16"/>
+   <object id="3" class="platform" x="0" y="8" width="32" height="8">
+    <properties>
+     <property name="one_way" type="bool" value="true"/>
+    </properties>
+   </object>
+  </objectgroup>
+ </group>
+</map>
+"#;
+
+    const TMJ: &str = r#"{
+  "width": 4,
+  "height": 2,
+  "tilewidth": 16,
+  "tileheight": 16,
+  "infinite": false,
+  "tilesets": [
+    {
+      "firstgid": 1,
+      "image": "tiles.png",
+      "tilewidth": 16,
+      "tileheight": 16,
+      "spacing": 1,
+      "columns": 2,
+      "tilecount": 4
+    }
+  ],
+  "layers": [
+    {
+      "type": "tilelayer",
+      "name": "Ground",
+      "data": [0, 0, 0, 0, 1, 2, 2, 1],
+      "properties": [{ "name": "collision", "type": "bool", "value": true }]
+    },
+    {
+      "type": "group",
+      "layers": [
+        {
+          "type": "objectgroup",
+          "objects": [
+            { "name": "player", "type": "spawn", "x": 16, "y": 0, "width": 16, "height": 16 },
+            {
+              "class": "platform",
+              "x": 0,
+              "y": 8,
+              "width": 32,
+              "height": 8,
+              "properties": [{ "name": "one_way", "type": "bool", "value": true }]
+            }
+          ]
+        }
+      ]
+    },
+    { "type": "imagelayer", "image": "background.png" }
+  ]
+}"#;
+
+    /// Both test maps describe the same level
+    fn check_map(map: &TiledMap) {
+        assert_eq!((map.width, map.height), (4, 2));
+        assert_eq!((map.tile_width, map.tile_height), (16.0, 16.0));
+
+        assert_eq!(map.tilesets.len(), 1);
+        let tileset = &map.tilesets[0];
+        assert_eq!(tileset.first_gid, 1);
+        assert_eq!(tileset.image, "tiles.png");
+        assert_eq!(tileset.spacing, 1.0);
+        assert_eq!((tileset.columns, tileset.tile_count), (2, 4));
+
+        assert_eq!(map.layers.len(), 2);
+        match &map.layers[0] {
+            TiledLayer::Tiles {
+                name,
+                visible,
+                data,
+                properties,
+            } => {
+                assert_eq!(name, "Ground");
+                assert!(*visible);
+                assert_eq!(data, &[0, 0, 0, 0, 1, 2, 2, 1]);
+                assert!(parse_bool(properties.get("collision")));
+            }
+            layer => panic!("expected a tile layer, got {:?}", layer),
+        }
+
+        match &map.layers[1] {
+            TiledLayer::Objects { objects } => {
+                assert_eq!(objects.len(), 2);
+
+                assert_eq!(objects[0].name, "player");
+                assert_eq!(objects[0].class, "spawn");
+                assert_eq!((objects[0].x, objects[0].y), (16.0, 0.0));
+
+                assert_eq!(objects[1].class, "platform");
+                assert_eq!((objects[1].width, objects[1].height), (32.0, 8.0));
+                assert!(parse_bool(objects[1].properties.get("one_way")));
+            }
+            layer => panic!("expected an object layer, got {:?}", layer),
+        }
+    }
+
+    #[test]
+    fn parse_tmx_map() {
+        check_map(&parse_tmx(TMX.as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn parse_tmj_map() {
+        check_map(&parse_tmj(TMJ.as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn reject_infinite_maps() {
+        assert!(parse_tmx(TMX.replace(r#"infinite="0""#, r#"infinite="1""#).as_bytes()).is_err());
+        assert!(parse_tmj(
+            TMJ.replace(r#""infinite": false"#, r#""infinite": true"#)
+                .as_bytes()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn reject_external_tilesets() {
+        let tmx = r#"<map width="1" height="1" tilewidth="16" tileheight="16">
+ <tileset firstgid="1" source="tiles.tsx"/>
+</map>"#;
+        assert!(parse_tmx(tmx.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn reject_non_csv_layers() {
+        let tmx = TMX.replace(r#"encoding="csv""#, r#"encoding="base64""#);
+        assert!(parse_tmx(tmx.as_bytes()).is_err());
+    }
+}
@@ -2,11 +2,13 @@
 
 mod components;
 mod events;
+mod level;
 mod resources;
 mod states;
 mod systems;
 
 use bevy::diagnostic::*;
+use bevy::input::InputSystem;
 use bevy::prelude::*;
 use bevy::window::PresentMode;
 use bevy_egui::{EguiPlugin, EguiSettings};
@@ -15,34 +17,75 @@ use bevy_inspector_egui_rapier::InspectableRapierPlugin;
 use bevy_prototype_lyon::prelude::*;
 use bevy_rapier2d::prelude::*;
 
+use core_lib::components::ability::*;
 use core_lib::components::character::*;
+use core_lib::components::world::*;
 use core_lib::events::debug::*;
 use core_lib::resources::debug::*;
+use core_lib::resources::input::*;
+use core_lib::resources::prefab::*;
 use core_lib::systems::input::*;
+use core_lib::systems::prefab::*;
 
+use components::character::*;
 use components::game::*;
+use components::level::*;
+use components::world::*;
 use events::character::*;
 use events::*;
+use level::*;
+use resources::level::*;
+use resources::physics::*;
 use states::*;
+use systems::ability::*;
 use systems::character::*;
 use systems::debug::*;
+use systems::editor::*;
+use systems::level::*;
+use systems::world::*;
 use systems::*;
 
 // physics layers
 const WORLD_LAYER: u32 = 0b01;
 const CHARACTER_LAYER: u32 = 0b10;
 
-const CHARACTER_MASS: f32 = 100.0;
 const CHARACTER_GRAVITY: f32 = -0.0;
-const CHARACTER_JUMP_ACCELERATION: f32 = 40.0;
 
 const WINDOW_WIDTH: f32 = 1280.0;
 const WINDOW_HEIGHT: f32 = 720.0;
 const ORTHO_SIZE: f32 = 10.0;
 
-fn setup(asset_server: Res<AssetServer>) {
+const LEVELS: &[&str] = &["levels/arena.level.ron", "levels/tower.level.ron"];
+const PREFABS: &[&str] = &["prefabs/default.prefabs.ron"];
+
+/// Parses the `--seed <n>` command line argument
+fn parse_seed() -> Option<u64> {
+    let mut args = std::env::args().skip_while(|arg| arg != "--seed").skip(1);
+    let seed = args.next()?;
+
+    match seed.parse() {
+        Ok(seed) => Some(seed),
+        Err(err) => {
+            warn!("invalid seed '{}': {}", seed, err);
+            None
+        }
+    }
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
     #[cfg(debug_assertions)]
     asset_server.watch_for_changes().unwrap();
+
+    commands.insert_resource(ActionMap::default());
+    commands.insert_resource(ActionState::default());
+    commands.insert_resource(LevelProgress::new(LEVELS.iter().copied()));
+    commands.insert_resource(LevelGenerator::new(parse_seed()));
+    commands.insert_resource(PrefabRegistry::new(
+        PREFABS
+            .iter()
+            .map(|path| asset_server.load(*path))
+            .collect(),
+    ));
 }
 
 fn setup_debug(mut commands: Commands) {
@@ -67,9 +110,12 @@ fn main() {
         // plugins
         .add_plugins(DefaultPlugins)
         .add_plugin(ShapePlugin)
-        .add_plugin(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(
+        .add_plugin(RapierPhysicsPlugin::<PhysicsHooksData>::pixels_per_meter(
             WINDOW_HEIGHT / (ORTHO_SIZE * 2.0),
         ))
+        .insert_resource(PhysicsHooksWithQueryResource::<PhysicsHooksData>(Box::new(
+            GamePhysicsHooks,
+        )))
         .add_plugin(RapierDebugRenderPlugin::default())
         .add_plugin(InspectableRapierPlugin)
         .add_plugin(FrameTimeDiagnosticsPlugin)
@@ -87,13 +133,41 @@ fn main() {
         })
         .add_plugin(WorldInspectorPlugin::new())
         .register_inspectable::<TimerText>()
+        .register_inspectable::<LevelEntity>()
+        .register_inspectable::<LevelTrigger>()
+        .register_inspectable::<LevelExit>()
+        .register_inspectable::<Climbable>()
+        .register_inspectable::<Water>()
+        .register_inspectable::<Hazard>()
+        .register_inspectable::<LevelPlatform>()
+        .register_inspectable::<OneWayPlatform>()
+        .register_inspectable::<DropThrough>()
+        .register_inspectable::<Crouching>()
         .register_inspectable::<Character>()
         .register_inspectable::<PlayerCharacter>()
         .register_inspectable::<NonPlayerCharacter>()
+        .register_inspectable::<ScreenWrap>()
+        .register_inspectable::<SurfaceMaterial>()
+        .register_inspectable::<WrapGhost>()
+        .register_inspectable::<MultiJump>()
+        .register_inspectable::<Dash>()
+        .register_inspectable::<Invulnerable>()
+        // assets
+        .add_asset::<PrefabSet>()
+        .init_asset_loader::<PrefabSetLoader>()
+        .add_asset::<LevelAsset>()
+        .init_asset_loader::<LevelAssetLoader>()
+        .init_asset_loader::<tiled::TiledMapLoader>()
+        .init_asset_loader::<ldtk::LdtkProjectLoader>()
         // events
         .add_event::<ToggleDebugEvent>()
         .add_event::<PauseEvent>()
         .add_event::<JumpEvent>()
+        .add_event::<JumpReleaseEvent>()
+        .add_event::<DashEvent>()
+        .add_event::<HitEvent>()
+        .add_event::<WaterEnterEvent>()
+        .add_event::<WaterExitEvent>()
         // game states
         .add_state(GameState::Menu)
         .add_system_set(
@@ -118,6 +192,21 @@ fn main() {
             SystemSet::on_update(GameState::Game)
                 .with_system(states::game::on_update)
                 .with_system(states::game::update_ui)
+                // level
+                .with_system(
+                    regenerate_level
+                        .label(systems::Systems::RegenerateLevel)
+                        .before(systems::Systems::ReloadLevel),
+                )
+                .with_system(reload_level.label(systems::Systems::ReloadLevel))
+                .with_system(spawn_level.after(systems::Systems::ReloadLevel))
+                .with_system(level_exit.before(systems::Systems::RegenerateLevel))
+                .with_system(hazard_contact)
+                .with_system(moving_platforms)
+                // world
+                .with_system(spawn_wrap_ghosts)
+                .with_system(screen_wrap.label(systems::Systems::ScreenWrap))
+                .with_system(update_wrap_ghosts.after(systems::Systems::ScreenWrap))
                 // input
                 .with_system(pause_input.label(systems::Systems::PauseInput))
                 .with_system(
@@ -125,12 +214,46 @@ fn main() {
                         .label(systems::Systems::Pause)
                         .after(systems::Systems::PauseInput),
                 )
-                .with_system(platformer_2d_keyboard_input.label(systems::Systems::CharacterInput))
-                .with_system(character_movement.after(systems::Systems::CharacterInput))
+                .with_system(platformer_2d_input.label(systems::Systems::CharacterInput))
+                .with_system(
+                    character_movement
+                        .label(systems::Systems::CharacterMotion)
+                        .after(systems::Systems::CharacterInput),
+                )
+                .with_system(
+                    character_climb
+                        .label(systems::Systems::CharacterMotion)
+                        .after(systems::Systems::CharacterInput),
+                )
                 .with_system(jump_input.label(systems::Systems::CharacterJumpInput))
-                .with_system(character_jump.after(systems::Systems::CharacterJumpInput))
+                .with_system(
+                    character_jump
+                        .label(systems::Systems::CharacterMotion)
+                        .after(systems::Systems::CharacterJumpInput),
+                )
+                .with_system(
+                    dash_input
+                        .label(systems::Systems::CharacterDashInput)
+                        .after(systems::Systems::CharacterInput),
+                )
+                .with_system(
+                    character_dash
+                        .label(systems::Systems::CharacterMotion)
+                        .after(systems::Systems::CharacterDashInput),
+                )
+                .with_system(ability_pickup)
                 // physics
                 .with_system(character_grounded_system)
+                .with_system(character_drop_through.after(systems::Systems::CharacterInput))
+                .with_system(character_crouch.after(systems::Systems::CharacterInput))
+                .with_system(character_wall_system)
+                .with_system(character_water)
+                .with_system(character_hit.label(systems::Systems::CharacterMotion))
+                .with_system(character_wall_slide.label(systems::Systems::CharacterMotion))
+                .with_system(character_controller_mode)
+                .with_system(
+                    kinematic_character_controller.after(systems::Systems::CharacterMotion),
+                )
                 .with_system(character_gravity_multiplier),
         )
         .add_system_set(
@@ -159,6 +282,13 @@ fn main() {
                 .with_system(states::paused::teardown_ui)
                 .with_system(states::paused::teardown),
         )
+        .add_system_set(SystemSet::on_enter(GameState::Editor).with_system(states::editor::setup))
+        .add_system_set(
+            SystemSet::on_update(GameState::Editor)
+                .with_system(editor_input)
+                .with_system(editor_ui),
+        )
+        .add_system_set(SystemSet::on_exit(GameState::Editor).with_system(states::editor::teardown))
         .add_system_set(
             SystemSet::on_enter(GameState::GameOver)
                 .with_system(states::gameover::setup)
@@ -174,9 +304,28 @@ fn main() {
                 // TODO: this is gross but it's the best "complete" cleanup from the game over sub-state
                 .with_system(core_lib::states::teardown),
         )
+        .add_system_set(
+            SystemSet::on_enter(GameState::Victory)
+                .with_system(states::victory::setup)
+                .with_system(states::victory::setup_ui),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Victory).with_system(states::victory::on_update),
+        )
+        .add_system_set(
+            SystemSet::on_exit(GameState::Victory)
+                .with_system(states::victory::teardown_ui)
+                .with_system(states::victory::teardown)
+                // same gross cleanup as the game over sub-state
+                .with_system(core_lib::states::teardown),
+        )
         // setup
         .add_startup_system(setup)
         .add_startup_system(setup_debug)
+        // input
+        .add_system_to_stage(CoreStage::PreUpdate, update_action_state.after(InputSystem))
+        // prefabs
+        .add_system(update_prefab_registry)
         // debug
         .add_system(debug_system)
         .add_system(debug_ui)
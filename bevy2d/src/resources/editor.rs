@@ -0,0 +1,41 @@
+//! Level editor resources
+
+use bevy::prelude::*;
+
+/// What clicking in the world does while editing
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EditorTool {
+    /// Select and drag platforms and spawn points
+    Select,
+
+    /// Place new platforms
+    PlacePlatform,
+
+    /// Place new spawn points
+    PlaceSpawn,
+}
+
+impl Default for EditorTool {
+    fn default() -> Self {
+        Self::Select
+    }
+}
+
+/// Level editor state
+#[derive(Default)]
+pub struct EditorState {
+    /// The current tool
+    pub tool: EditorTool,
+
+    /// The prefab placed by the spawn point tool
+    pub spawn_prefab: String,
+
+    /// The selected platform or spawn point
+    pub selected: Option<Entity>,
+
+    /// The cursor offset from the selected entity while dragging it
+    pub drag_offset: Option<Vec2>,
+
+    /// Status message from the last save
+    pub status: String,
+}
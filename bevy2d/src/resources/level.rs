@@ -0,0 +1,86 @@
+//! Level resources
+
+use bevy::prelude::*;
+
+use crate::level::generator::GeneratorSettings;
+use crate::level::LevelAsset;
+
+/// The currently loaded level
+pub struct CurrentLevel {
+    /// The level asset handle
+    pub handle: Handle<LevelAsset>,
+
+    /// Has the level been spawned yet?
+    pub spawned: bool,
+}
+
+impl CurrentLevel {
+    /// Creates a new, unspawned, current level
+    pub fn new(handle: Handle<LevelAsset>) -> Self {
+        Self {
+            handle,
+            spawned: false,
+        }
+    }
+}
+
+/// Tracks progress through the list of levels
+///
+/// This persists across game state changes
+pub struct LevelProgress {
+    /// The level asset paths, in order
+    pub levels: Vec<String>,
+
+    /// The index of the current level
+    pub current: usize,
+}
+
+impl LevelProgress {
+    /// Creates a new level progression starting at the first level
+    pub fn new(levels: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        Self {
+            levels: levels.into_iter().map(Into::into).collect(),
+            current: 0,
+        }
+    }
+
+    /// Returns the asset path of the current level
+    pub fn current_level(&self) -> &str {
+        &self.levels[self.current]
+    }
+
+    /// Is the current level the last level?
+    pub fn is_last_level(&self) -> bool {
+        self.current + 1 >= self.levels.len()
+    }
+}
+
+/// Procedural level generation state
+///
+/// This persists across game state changes
+#[derive(Default)]
+pub struct LevelGenerator {
+    /// The seed to generate levels from, levels are loaded from the level list if this is None
+    pub seed: Option<u64>,
+
+    /// The seed the current level was generated from
+    pub generated: Option<u64>,
+
+    /// Generator settings
+    pub settings: GeneratorSettings,
+}
+
+impl LevelGenerator {
+    /// Creates a new level generator, optionally seeded
+    pub fn new(seed: Option<u64>) -> Self {
+        Self {
+            seed,
+            ..Default::default()
+        }
+    }
+
+    /// Does the current level need to be replaced?
+    pub fn is_dirty(&self) -> bool {
+        self.seed != self.generated
+    }
+}
@@ -1,4 +1,7 @@
 //! ECS resources
 
+pub mod editor;
 pub mod game;
+pub mod level;
+pub mod physics;
 pub mod world;
@@ -0,0 +1,51 @@
+//! Physics resources
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+use bevy_rapier2d::rapier::math::Vector;
+
+use crate::components::character::*;
+use crate::components::level::*;
+
+/// The components available to the physics hooks
+pub type PhysicsHooksData<'a> = (Option<&'a OneWayPlatform>, Option<&'a DropThrough>);
+
+/// The allowed contact normal deviation for one-way platforms (radians)
+const ONE_WAY_PLATFORM_ANGLE: f32 = 0.1;
+
+/// Contact modification physics hooks
+///
+/// Only colliders with `ActiveHooks::MODIFY_SOLVER_CONTACTS` are run through this
+pub struct GamePhysicsHooks;
+
+impl<'a> PhysicsHooksWithQuery<PhysicsHooksData<'a>> for GamePhysicsHooks {
+    fn modify_solver_contacts(
+        &self,
+        mut context: ContactModificationContextView,
+        data: &Query<PhysicsHooksData<'a>>,
+    ) {
+        let one_way = |entity: Entity| matches!(data.get(entity), Ok((Some(_), _)));
+        let dropping = |entity: Entity| matches!(data.get(entity), Ok((_, Some(_))));
+
+        let (collider1, collider2) = (context.collider1(), context.collider2());
+
+        // the normal points from the first collider to the second
+        let (platform, other, allowed_normal) = if one_way(collider1) {
+            (collider1, collider2, Vector::y())
+        } else if one_way(collider2) {
+            (collider2, collider1, -Vector::y())
+        } else {
+            return;
+        };
+
+        if dropping(other) {
+            debug!("dropping through {:?}", platform);
+            context.raw.solver_contacts.clear();
+            return;
+        }
+
+        context
+            .raw
+            .update_as_oneway_platform(&allowed_normal, ONE_WAY_PLATFORM_ANGLE);
+    }
+}
@@ -1,9 +1,12 @@
 //! Game... world... ? resources
 
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::ORTHO_SIZE;
 
 /// World bounding box
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Deserialize, Serialize)]
 pub struct WorldBounds2D {
     /// The bottom left of the world
     pub min: Vec2,
@@ -13,6 +16,16 @@ pub struct WorldBounds2D {
 }
 
 impl WorldBounds2D {
+    /// Returns the default world bounds, the camera view of the window
+    pub fn from_window(window: &Window) -> Self {
+        let aspect_ratio = window.width() / window.height();
+
+        Self {
+            min: Vec2::new(-aspect_ratio * ORTHO_SIZE, -ORTHO_SIZE),
+            max: Vec2::new(aspect_ratio * ORTHO_SIZE, ORTHO_SIZE),
+        }
+    }
+
     /// Returns the width of the world
     pub fn width(&self) -> f32 {
         self.max.x - self.min.x
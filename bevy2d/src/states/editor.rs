@@ -0,0 +1,53 @@
+//! Level editor sub-state systems
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use core_lib::components::character::*;
+
+use crate::components::level::*;
+use crate::resources::editor::*;
+
+/// Editor setup
+pub fn setup(
+    mut commands: Commands,
+    characters: Query<Entity, With<Character>>,
+    mut spawn_points: Query<&mut Visibility, With<SpawnPoint>>,
+) {
+    commands.insert_resource(EditorState {
+        spawn_prefab: "player".to_owned(),
+        ..Default::default()
+    });
+
+    // freeze every character so nothing moves while editing
+    for entity in characters.iter() {
+        commands
+            .entity(entity)
+            .remove::<RigidBody>()
+            .insert(RigidBody::Fixed);
+    }
+
+    for mut visibility in spawn_points.iter_mut() {
+        visibility.is_visible = true;
+    }
+}
+
+/// Editor tear down
+pub fn teardown(
+    mut commands: Commands,
+    characters: Query<(Entity, &Character)>,
+    mut spawn_points: Query<&mut Visibility, With<SpawnPoint>>,
+) {
+    commands.remove_resource::<EditorState>();
+
+    for (entity, character) in characters.iter() {
+        commands
+            .entity(entity)
+            .remove::<RigidBody>()
+            .insert(character.rigid_body());
+    }
+
+    for mut visibility in spawn_points.iter_mut() {
+        visibility.is_visible = false;
+    }
+}
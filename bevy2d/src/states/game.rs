@@ -4,17 +4,14 @@ use bevy::prelude::*;
 use bevy::render::camera::*;
 use bevy_rapier2d::prelude::*;
 
-use core_lib::components::character::*;
 use core_lib::resources::input::*;
 
 use crate::components::game::*;
 use crate::resources::game::*;
+use crate::resources::level::*;
 use crate::resources::world::*;
 use crate::states::*;
-use crate::{
-    CHARACTER_GRAVITY, CHARACTER_JUMP_ACCELERATION, CHARACTER_LAYER, CHARACTER_MASS, ORTHO_SIZE,
-    WORLD_LAYER,
-};
+use crate::{CHARACTER_GRAVITY, ORTHO_SIZE};
 
 /// Main game state
 #[derive(Default)]
@@ -22,6 +19,13 @@ pub struct Game {
     timer: Timer,
 }
 
+impl Game {
+    /// Restarts the level timer
+    pub fn reset_timer(&mut self) {
+        self.timer.reset();
+    }
+}
+
 /// Game setup
 pub fn setup(mut commands: Commands) {
     info!("camera size: {}", ORTHO_SIZE);
@@ -58,145 +62,41 @@ pub fn teardown(mut commands: Commands) {
 }
 
 /// Setup the game world
-pub fn setup_world(mut commands: Commands, windows: Res<Windows>) {
-    let window = windows.get_primary().unwrap();
-    let aspect_ratio = window.width() / window.height();
-
-    let world_bounds = WorldBounds2D {
-        min: Vec2::new(-aspect_ratio * ORTHO_SIZE, -ORTHO_SIZE),
-        max: Vec2::new(aspect_ratio * ORTHO_SIZE, ORTHO_SIZE),
-    };
+pub fn setup_world(
+    mut commands: Commands,
+    windows: Res<Windows>,
+    asset_server: Res<AssetServer>,
+    progress: Res<LevelProgress>,
+    generator: Res<LevelGenerator>,
+) {
+    // levels may override this
+    let world_bounds = WorldBounds2D::from_window(windows.get_primary().unwrap());
     info!("world bounds: {:?}", world_bounds);
 
     // world
     commands.insert_resource(world_bounds);
 
-    // ground
-    commands
-        .spawn_bundle(SpriteBundle {
-            sprite: Sprite {
-                color: Color::GREEN,
-                custom_size: Some(Vec2::new(world_bounds.width(), 1.0)),
-                ..Default::default()
-            },
-            transform: Transform::from_translation(Vec3::new(0.0, world_bounds.min.y + 0.5, 0.0)),
-            ..Default::default()
-        })
-        // rigidbody
-        .insert(RigidBody::Fixed)
-        .insert(LockedAxes::ROTATION_LOCKED)
-        //.insert(RigidBodyPositionSync::Discrete)
-        // collider
-        .insert(Collider::cuboid(world_bounds.width() / 2.0, 0.5))
-        //.insert(ColliderMaterial::new(0.0, 0.0))
-        .insert(CollisionGroups::new(WORLD_LAYER, CHARACTER_LAYER))
-        .insert(Name::new("Ground"));
-
-    // platforms
-    commands
-        .spawn_bundle(SpriteBundle {
-            sprite: Sprite {
-                color: Color::YELLOW,
-                custom_size: Some(Vec2::new(5.0, 1.0)),
-                ..Default::default()
-            },
-            transform: Transform::from_translation(Vec3::new(0.0, 0.0, 0.0)),
-            ..Default::default()
-        })
-        // rigidbody
-        .insert(RigidBody::Fixed)
-        .insert(LockedAxes::ROTATION_LOCKED)
-        //.insert(RigidBodyPositionSync::Discrete)
-        // collider
-        .insert(Collider::cuboid(2.5, 0.5))
-        //.insert(ColliderMaterial::new(0.0, 0.0))
-        .insert(CollisionGroups::new(WORLD_LAYER, CHARACTER_LAYER))
-        .insert(Name::new("Platform"));
-
-    commands
-        .spawn_bundle(SpriteBundle {
-            sprite: Sprite {
-                color: Color::CYAN,
-                custom_size: Some(Vec2::new(5.0, 1.0)),
-                ..Default::default()
-            },
-            transform: Transform::from_translation(Vec3::new(-10.0, -5.0, 0.0)),
-            ..Default::default()
-        })
-        // rigidbody
-        .insert(RigidBody::Fixed)
-        .insert(LockedAxes::ROTATION_LOCKED)
-        //.insert(RigidBodyPositionSync::Discrete)
-        // collider
-        .insert(Collider::cuboid(2.5, 0.5))
-        //.insert(ColliderMaterial::new(0.0, 0.0))
-        .insert(CollisionGroups::new(WORLD_LAYER, CHARACTER_LAYER))
-        .insert(Name::new("Platform"));
+    // level, seeded levels are generated once the prefabs are loaded
+    if generator.seed.is_some() {
+        commands.insert_resource(CurrentLevel::new(Handle::default()));
+        return;
+    }
 
-    commands
-        .spawn_bundle(SpriteBundle {
-            sprite: Sprite {
-                color: Color::CYAN,
-                custom_size: Some(Vec2::new(5.0, 1.0)),
-                ..Default::default()
-            },
-            transform: Transform::from_translation(Vec3::new(10.0, -5.0, 0.0)),
-            ..Default::default()
-        })
-        // rigidbody
-        .insert(RigidBody::Fixed)
-        .insert(LockedAxes::ROTATION_LOCKED)
-        //.insert(RigidBodyPositionSync::Discrete)
-        // collider
-        .insert(Collider::cuboid(2.5, 0.5))
-        //.insert(ColliderMaterial::new(0.0, 0.0))
-        .insert(CollisionGroups::new(WORLD_LAYER, CHARACTER_LAYER))
-        .insert(Name::new("Platform"));
-
-    // player
-    commands
-        .spawn_bundle(SpriteBundle {
-            sprite: Sprite {
-                color: Color::BLUE,
-                custom_size: Some(Vec2::new(1.0, 2.0)),
-                ..Default::default()
-            },
-            transform: Transform::from_translation(Vec3::new(
-                world_bounds.min.x + 1.0,
-                world_bounds.min.y + 10.0,
-                0.0,
-            )),
-            ..Default::default()
-        })
-        // rigidbody
-        .insert(RigidBody::Dynamic)
-        .insert(LockedAxes::ROTATION_LOCKED)
-        .insert(AdditionalMassProperties::MassProperties(MassProperties {
-            local_center_of_mass: Vec2::ZERO,
-            mass: CHARACTER_MASS,
-            principal_inertia: 0.0,
-        }))
-        .insert(Velocity::default())
-        //.insert(RigidBodyPositionSync::Discrete)
-        // collider
-        .insert(Collider::cuboid(0.5, 1.0))
-        //.insert(ColliderMaterial::new(0.0, 0.0))
-        .insert(CollisionGroups::new(CHARACTER_LAYER, WORLD_LAYER))
-        .insert(Character {
-            speed: 10.0,
-            air_control_factor: 1.0,
-            jump_force: Vec2::Y * CHARACTER_JUMP_ACCELERATION * CHARACTER_MASS,
-            ..Default::default()
-        })
-        // forces
-        .insert(ExternalForce::default())
-        .insert(ExternalImpulse::default())
-        .insert(PlayerCharacter::default())
-        .insert(Name::new("Player"));
+    info!(
+        "loading level {}: {}",
+        progress.current,
+        progress.current_level()
+    );
+    commands.insert_resource(CurrentLevel::new(
+        asset_server.load(progress.current_level()),
+    ));
 }
 
 /// Tear down the game world
-pub fn teardown_world(mut commands: Commands) {
+pub fn teardown_world(mut commands: Commands, mut generator: ResMut<LevelGenerator>) {
+    generator.generated = None;
+
+    commands.remove_resource::<CurrentLevel>();
     commands.remove_resource::<WorldBounds2D>();
 }
 
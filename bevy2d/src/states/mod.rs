@@ -1,9 +1,11 @@
 //! Game states
 
+pub mod editor;
 pub mod game;
 pub mod gameover;
 pub mod menu;
 pub mod paused;
+pub mod victory;
 
 /// The game state
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
@@ -11,5 +13,7 @@ pub enum GameState {
     Menu,
     Game,
     Paused,
+    Editor,
     GameOver,
+    Victory,
 }
@@ -0,0 +1,61 @@
+//! Victory sub-state systems
+
+use bevy::prelude::*;
+
+use crate::resources::level::*;
+use crate::states::*;
+
+pub struct VictoryTimer(Timer);
+
+/// Victory setup
+pub fn setup(mut commands: Commands) {
+    commands.insert_resource(VictoryTimer(Timer::from_seconds(10.0, false)));
+}
+
+/// Victory tear down
+pub fn teardown(mut commands: Commands, mut progress: ResMut<LevelProgress>) {
+    commands.remove_resource::<VictoryTimer>();
+
+    // start over from the beginning next time
+    progress.current = 0;
+}
+
+/// Setup the victory UI
+pub fn setup_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+    // TODO: this should be centered
+    commands.spawn_bundle(TextBundle {
+        style: Style {
+            align_self: AlignSelf::FlexEnd,
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(5.0),
+                right: Val::Px(15.0),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        text: Text::from_section(
+            "Victory!",
+            TextStyle {
+                font: asset_server.load("fonts/Roboto-Regular.ttf"),
+                font_size: 30.0,
+                color: Color::WHITE,
+            },
+        ),
+        ..Default::default()
+    });
+}
+
+/// Tear down the victory UI
+pub fn teardown_ui(mut _commands: Commands) {}
+
+/// Victory state update
+pub fn on_update(
+    time: Res<Time>,
+    mut timer: ResMut<VictoryTimer>,
+    mut state: ResMut<State<GameState>>,
+) {
+    if timer.0.tick(time.delta()).just_finished() {
+        state.set(GameState::Menu).unwrap();
+    }
+}
@@ -0,0 +1,112 @@
+//! Character ability systems
+
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use core_lib::components::ability::*;
+use core_lib::components::character::*;
+use core_lib::resources::input::*;
+
+use crate::events::character::*;
+
+/// Handles dash input
+pub fn dash_input(
+    action_state: Res<ActionState>,
+    input: Res<CharacterInput2D>,
+    mut dash_events: EventWriter<DashEvent>,
+) {
+    if action_state.just_pressed(Action::Dash) {
+        dash_events.send(DashEvent {
+            direction: input.direction,
+        });
+    }
+}
+
+/// Handles player character dash events
+pub fn character_dash(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut dash_events: EventReader<DashEvent>,
+    mut query: Query<
+        (Entity, &mut Dash, &mut Velocity, Option<&Invulnerable>),
+        With<PlayerCharacter>,
+    >,
+) {
+    let direction = dash_events.iter().last().map(|event| event.direction);
+
+    for (entity, mut dash, mut velocity, invulnerable) in query.iter_mut() {
+        dash.update(time.delta_seconds());
+
+        if let Some(direction) = direction {
+            // no direction dashes the way the character is already moving,
+            // a character standing still doesn't dash at all
+            let direction = if direction == Vec2::ZERO {
+                if velocity.linvel.x.abs() > f32::EPSILON {
+                    Vec2::X * velocity.linvel.x.signum()
+                } else {
+                    Vec2::ZERO
+                }
+            } else {
+                direction
+            };
+
+            if dash.try_start(direction) {
+                debug!("dash {:?}", direction);
+            }
+        }
+
+        if dash.is_dashing() {
+            velocity.linvel = dash.velocity();
+        }
+
+        match (dash.is_invulnerable(), invulnerable.is_some()) {
+            (true, false) => {
+                commands.entity(entity).insert(Invulnerable);
+            }
+            (false, true) => {
+                commands.entity(entity).remove::<Invulnerable>();
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Grants abilities to player characters that touch an ability pickup
+pub fn ability_pickup(
+    mut commands: Commands,
+    rapier_context: Res<RapierContext>,
+    pickups: Query<(Entity, &AbilityPickup)>,
+    players: Query<Entity, With<PlayerCharacter>>,
+) {
+    for (pickup_entity, pickup) in pickups.iter() {
+        for player in players.iter() {
+            if rapier_context.intersection_pair(pickup_entity, player) != Some(true) {
+                continue;
+            }
+
+            info!("ability pickup: {:?}", pickup);
+
+            let mut entity = commands.entity(player);
+            match pickup {
+                AbilityPickup::MultiJump(multi_jump) => {
+                    // picked up mid-air the jumps are usable right away
+                    let mut multi_jump = multi_jump.clone();
+                    multi_jump.reset();
+                    entity.insert(multi_jump);
+                }
+                AbilityPickup::Dash(dash) => {
+                    entity.insert(dash.clone());
+                }
+                AbilityPickup::RemoveAll => {
+                    entity
+                        .remove::<MultiJump>()
+                        .remove::<Dash>()
+                        .remove::<Invulnerable>();
+                }
+            }
+
+            commands.entity(pickup_entity).despawn_recursive();
+            break;
+        }
+    }
+}
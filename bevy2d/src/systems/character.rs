@@ -3,101 +3,815 @@
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
+use core_lib::components::ability::*;
 use core_lib::components::character::*;
+use core_lib::components::world::*;
 use core_lib::resources::input::*;
 
+use crate::components::character::*;
+use crate::components::level::*;
 use crate::events::character::*;
 use crate::resources::game::*;
 use crate::resources::world::*;
 use crate::{CHARACTER_LAYER, WORLD_LAYER};
 
+/// How long a character ignores one-way platforms after dropping through one (seconds)
+const DROP_THROUGH_TIME: f32 = 0.25;
+
+/// How far below a character the ground can be and still count
+const GROUND_CHECK_DISTANCE: f32 = 0.1;
+
+/// Half the height of the ground check shape
+const GROUND_CHECK_SKIN: f32 = 0.02;
+
+/// How much of the character width is used for the ground check
+const GROUND_CHECK_WIDTH: f32 = 0.9;
+
+/// How much of the standing height a crouching character is
+const CROUCH_HEIGHT_FACTOR: f32 = 0.5;
+
+/// Returns the additional mass of a rigidbody, 1 if it doesn't have any
+fn additional_mass(mass: Option<&AdditionalMassProperties>) -> f32 {
+    match mass {
+        Some(AdditionalMassProperties::MassProperties(properties)) => properties.mass,
+        _ => 1.0,
+    }
+}
+
+/// Returns the half extents of a collider's bounding box
+pub fn collider_half_extents(collider: &Collider) -> Vec2 {
+    let half_extents = collider.raw.compute_local_aabb().half_extents();
+    Vec2::new(half_extents.x, half_extents.y)
+}
+
 /// Handles player character movement
+///
+/// Horizontal velocity is steered towards the input direction
+/// rather than set directly, so characters keep their momentum.
+/// Grounded characters walk along slopes, move along with moving platforms
+/// and conveyors and are affected by the surface material they're standing on.
+/// Characters on bouncy surfaces aren't stuck to the ground so they can bounce
+#[allow(clippy::too_many_arguments)]
 pub fn character_movement(
+    time: Res<Time>,
     input: Res<CharacterInput2D>,
     bounds: Res<WorldBounds2D>,
-    mut query: Query<(&Character, &Sprite, &GlobalTransform, &mut Velocity), With<PlayerCharacter>>,
+    platforms: Query<&MovingPlatform>,
+    materials: Query<&SurfaceMaterial>,
+    mut query: Query<
+        (
+            &Character,
+            &Collider,
+            &GlobalTransform,
+            &mut Velocity,
+            Option<&Dash>,
+            Option<&ScreenWrap>,
+        ),
+        With<PlayerCharacter>,
+    >,
 ) {
-    for (character, sprite, transform, mut velocity) in query.iter_mut() {
-        let half_width = sprite.custom_size.unwrap().x / 2.0;
-        let position = transform.translation();
-        let mut speed = character.speed;
+    for (character, collider, transform, mut velocity, dash, screen_wrap) in query.iter_mut() {
+        // let wall jumps carry the character away from the wall
+        if character.is_wall_jump_locked() {
+            continue;
+        }
+
+        // and knockback carry the character away from hits
+        if character.is_hit_stunned() {
+            continue;
+        }
+
+        if dash.map(|dash| dash.is_dashing()).unwrap_or_default() {
+            continue;
+        }
 
-        if (position.x - half_width < bounds.min.x && input.direction.x < 0.0)
-            || (position.x + half_width > bounds.max.x && input.direction.x > 0.0)
-        {
-            // TODO: actually what we want is to wrap around
-            speed = 0.0;
-        } else if !character.grounded {
-            // TODO: air control is kind of bad because we aren't factoring in momentum
-            speed *= character.air_control_factor;
+        // climbing characters are moved by character_climb
+        if character.climbing {
+            continue;
         }
 
-        velocity.linvel.x = input.direction.x * speed;
+        let half_width = collider_half_extents(collider).x;
+        let position = transform.translation();
+
+        // characters that don't wrap around stop at the edges of the world
+        let blocked = screen_wrap.is_none()
+            && ((position.x - half_width < bounds.min.x && input.direction.x < 0.0)
+                || (position.x + half_width > bounds.max.x && input.direction.x > 0.0));
+
+        let ground = character.ground_entity.filter(|_| character.grounded);
+        let surface = ground
+            .and_then(|ground| materials.get(ground).ok())
+            .copied()
+            .unwrap_or_default();
+        let tangent = character.ground_tangent();
+
+        let platform_velocity = ground
+            .and_then(|ground| platforms.get(ground).ok())
+            .map(|platform| platform.velocity)
+            .unwrap_or_default()
+            + tangent * surface.conveyor_speed;
+
+        // steer relative to whatever the character is standing on
+        let relative_velocity = velocity.linvel - platform_velocity;
+
+        // stick to the ground, but don't cancel jumps or bounces
+        if character.grounded && !character.is_jumping() && surface.restitution <= 0.0 {
+            let speed = if blocked {
+                0.0
+            } else {
+                character.steer(
+                    relative_velocity.dot(tangent),
+                    input.direction.x,
+                    &surface,
+                    time.delta_seconds(),
+                )
+            };
+
+            velocity.linvel = tangent * speed + platform_velocity;
+        } else {
+            let speed = if blocked {
+                0.0
+            } else {
+                character.steer(
+                    relative_velocity.x,
+                    input.direction.x,
+                    &surface,
+                    time.delta_seconds(),
+                )
+            };
+
+            velocity.linvel.x = speed + platform_velocity.x;
+        }
     }
 }
 
 /// Handles player character jump events
+///
+/// Jumps are buffered and allowed shortly after leaving the ground,
+/// releasing the jump early cuts the jump short
+///
+/// Characters with the multi-jump ability can also jump in the air,
+/// swimming characters do a swim stroke instead
 pub fn character_jump(
-    event_reader: EventReader<JumpEvent>,
-    mut query: Query<(&Character, &mut ExternalImpulse), With<PlayerCharacter>>,
+    time: Res<Time>,
+    mut jump_events: EventReader<JumpEvent>,
+    mut release_events: EventReader<JumpReleaseEvent>,
+    mut query: Query<
+        (
+            &mut Character,
+            &mut Velocity,
+            &mut ExternalImpulse,
+            Option<&mut MultiJump>,
+        ),
+        With<PlayerCharacter>,
+    >,
 ) {
-    if !event_reader.is_empty() {
-        event_reader.clear();
+    let jump = jump_events.iter().count() > 0;
+    let release = release_events.iter().count() > 0;
+
+    for (mut character, mut velocity, mut impulse, multi_jump) in query.iter_mut() {
+        if character.is_swimming() {
+            if jump {
+                impulse.impulse = character.swim_stroke_force;
+            }
+            continue;
+        }
+
+        if jump {
+            character.request_jump();
+        }
+
+        let mut jump_impulse = character.update_jump(time.delta_seconds());
 
-        for (character, mut impulse) in query.iter_mut() {
+        if let Some(mut multi_jump) = multi_jump {
             if character.grounded {
-                impulse.impulse = character.jump_force;
+                multi_jump.reset();
+            } else if jump && jump_impulse.is_none() && multi_jump.try_jump() {
+                jump_impulse = Some(character.force_jump());
             }
         }
+
+        if let Some(jump_impulse) = jump_impulse {
+            // don't let falling eat into the jump
+            velocity.linvel.y = velocity.linvel.y.max(0.0);
+            impulse.impulse = jump_impulse;
+            continue;
+        }
+
+        if velocity.linvel.y <= 0.0 {
+            // falling, so the jump is over
+            character.release_jump();
+        } else if release {
+            if let Some(factor) = character.release_jump() {
+                velocity.linvel.y *= factor;
+            }
+        }
+    }
+}
+
+/// Moves player characters up and down ladders
+///
+/// Characters grab on when they press up or down inside a climbable volume
+/// and let go when they jump, dash, leave the volume or walk off at the bottom.
+/// Gravity is suspended while climbing
+#[allow(clippy::type_complexity)]
+pub fn character_climb(
+    mut commands: Commands,
+    input: Res<CharacterInput2D>,
+    rapier_context: Res<RapierContext>,
+    climbables: Query<Entity, With<Climbable>>,
+    mut query: Query<
+        (
+            Entity,
+            &mut Character,
+            &mut Velocity,
+            Option<&mut GravityScale>,
+            Option<&Dash>,
+        ),
+        With<PlayerCharacter>,
+    >,
+) {
+    for (entity, mut character, mut velocity, gravity_scale, dash) in query.iter_mut() {
+        let dashing = dash.map(|dash| dash.is_dashing()).unwrap_or_default();
+        let on_climbable = climbables
+            .iter()
+            .any(|climbable| rapier_context.intersection_pair(climbable, entity) == Some(true));
+
+        let climbing = if !on_climbable || dashing || character.is_hit_stunned() {
+            false
+        } else if character.climbing {
+            !character.grounded || input.direction.y > 0.0
+        } else {
+            !character.is_jumping()
+                && (input.direction.y > 0.0 || (input.direction.y < 0.0 && !character.grounded))
+        };
+
+        if climbing != character.climbing {
+            debug!("climbing: {}", climbing);
+        }
+        character.climbing = climbing;
+
+        // jumping and getting hit let go of the ladder outside of this system,
+        // so gravity follows the climbing state rather than its transitions
+        let scale = if climbing { 0.0 } else { 1.0 };
+        match gravity_scale {
+            Some(mut gravity_scale) => {
+                if gravity_scale.0 != scale {
+                    gravity_scale.0 = scale;
+                }
+            }
+            None => {
+                commands.entity(entity).insert(GravityScale(scale));
+            }
+        }
+
+        if climbing {
+            velocity.linvel = input.direction * character.climb_speed;
+        }
+    }
+}
+
+/// Knocks characters back when they get hit
+///
+/// Invulnerable characters ignore hits
+#[allow(clippy::type_complexity)]
+pub fn character_hit(
+    time: Res<Time>,
+    mut events: EventReader<HitEvent>,
+    mut query: Query<(
+        &mut Character,
+        &GlobalTransform,
+        &mut Velocity,
+        &mut ExternalImpulse,
+        Option<&Invulnerable>,
+    )>,
+) {
+    for (mut character, ..) in query.iter_mut() {
+        character.update_hit_stun(time.delta_seconds());
+    }
+
+    for event in events.iter() {
+        let (mut character, transform, mut velocity, mut impulse, invulnerable) =
+            match query.get_mut(event.character) {
+                Ok(character) => character,
+                Err(_) => continue,
+            };
+
+        if invulnerable.is_some() {
+            continue;
+        }
+
+        debug!("hit from {}", event.source);
+
+        let direction = if event.source.x > transform.translation().x {
+            1.0
+        } else {
+            -1.0
+        };
+
+        // knockback shouldn't depend on how the character was moving
+        velocity.linvel = Vec2::ZERO;
+        impulse.impulse = character.hit(direction);
+    }
+}
+
+/// Swims characters that are in the water
+///
+/// Water pushes characters back up against gravity and slows them down.
+/// Characters entering or leaving the water send events
+pub fn character_water(
+    time: Res<Time>,
+    rapier_context: Res<RapierContext>,
+    rapier_config: Res<RapierConfiguration>,
+    mut enter_events: EventWriter<WaterEnterEvent>,
+    mut exit_events: EventWriter<WaterExitEvent>,
+    waters: Query<(Entity, &Water)>,
+    mut query: Query<(Entity, &mut Character, &mut Velocity)>,
+) {
+    let dt = time.delta_seconds();
+
+    for (entity, mut character, mut velocity) in query.iter_mut() {
+        let water = waters
+            .iter()
+            .find(|(water, _)| rapier_context.intersection_pair(*water, entity) == Some(true));
+
+        let water_entity = water.map(|(water, _)| water);
+        if water_entity != character.water_entity {
+            if let Some(water) = character.water_entity {
+                debug!("left the water");
+                exit_events.send(WaterExitEvent {
+                    character: entity,
+                    water,
+                });
+            }
+
+            if let Some(water) = water_entity {
+                debug!("entered the water");
+                enter_events.send(WaterEnterEvent {
+                    character: entity,
+                    water,
+                });
+            }
+
+            character.water_entity = water_entity;
+        }
+
+        if let Some((_, water)) = water {
+            // the character gravity multiplier is already off while swimming
+            velocity.linvel -= rapier_config.gravity * water.buoyancy * dt;
+            velocity.linvel /= 1.0 + water.drag * dt;
+        }
+    }
+}
+
+/// Caps how fast characters fall while sliding down a wall
+pub fn character_wall_slide(mut query: Query<(&Character, &mut Velocity)>) {
+    for (character, mut velocity) in query.iter_mut() {
+        if character.is_wall_sliding() {
+            velocity.linvel.y = velocity.linvel.y.max(-character.wall_slide_speed);
+        }
     }
 }
 
 /// Handles jump input
-pub fn jump_input(keyboard_input: Res<Input<KeyCode>>, mut jump_events: EventWriter<JumpEvent>) {
-    if keyboard_input.just_pressed(KeyCode::Space) {
+pub fn jump_input(
+    action_state: Res<ActionState>,
+    mut jump_events: EventWriter<JumpEvent>,
+    mut release_events: EventWriter<JumpReleaseEvent>,
+) {
+    if action_state.just_pressed(Action::Jump) {
         jump_events.send(JumpEvent);
     }
+
+    if action_state.just_released(Action::Jump) {
+        release_events.send(JumpReleaseEvent);
+    }
 }
 
 /// Characters fall faster for better mechanics
 /// without having to affect the gravity effects of everything else
 pub fn character_gravity_multiplier(
     game_config: Res<GameConfig>,
-    mut query: Query<(&Character, &mut ExternalForce)>,
+    mut query: Query<(&Character, &mut ExternalForce, Option<&Dash>)>,
 ) {
-    for (character, mut force) in query.iter_mut() {
-        if !character.grounded {
+    for (character, mut force, dash) in query.iter_mut() {
+        let dashing = dash.map(|dash| dash.is_dashing()).unwrap_or_default();
+        if !character.grounded && !character.climbing && !character.is_swimming() && !dashing {
             force.force += game_config.character_gravity;
         }
     }
 }
 
 /// Checks whether a character is on the ground or not
+///
+/// Casts a thin box down from the character's feet so that edges still count as ground,
+/// slopes steeper than the character's max slope angle don't.
+/// One-way platforms only count while the character isn't rising or dropping through them
+#[allow(clippy::type_complexity)]
 pub fn character_grounded_system(
     rapier_context: Res<RapierContext>,
-    mut query: Query<(&mut Character, &Sprite, &Transform)>,
+    mut query: Query<(
+        &mut Character,
+        &Collider,
+        &Transform,
+        Option<&Velocity>,
+        Option<&DropThrough>,
+    )>,
+    one_way_platforms: Query<(), With<OneWayPlatform>>,
+    transforms: Query<&GlobalTransform>,
 ) {
-    for (mut character, sprite, transform) in query.iter_mut() {
-        let half_height = sprite.custom_size.unwrap().y / 2.0;
-
-        let position = transform.translation;
-
-        if let Some((_entity, _toi)) = rapier_context.cast_ray(
-            Vec2::new(position.x, position.y - half_height),
-            Vec2::Y * -1.0,
-            0.1,
-            true,
-            QueryFilter::new().groups(InteractionGroups::new(CHARACTER_LAYER, WORLD_LAYER)),
-        ) {
+    for (mut character, collider, transform, velocity, drop_through) in query.iter_mut() {
+        let half_extents = collider_half_extents(collider);
+
+        let position = transform.translation.truncate();
+
+        let rising = velocity
+            .map(|velocity| velocity.linvel.y > 0.0)
+            .unwrap_or_default();
+        let ignore_one_way = rising || drop_through.is_some();
+        let predicate = |entity| !ignore_one_way || !one_way_platforms.contains(entity);
+
+        // start the cast just inside the character so it isn't already touching the ground
+        let shape = Collider::cuboid(half_extents.x * GROUND_CHECK_WIDTH, GROUND_CHECK_SKIN);
+        let hit = rapier_context
+            .cast_shape(
+                position - Vec2::Y * (half_extents.y - GROUND_CHECK_SKIN * 2.0),
+                0.0,
+                Vec2::NEG_Y,
+                &shape,
+                GROUND_CHECK_DISTANCE + GROUND_CHECK_SKIN,
+                QueryFilter::new()
+                    .groups(InteractionGroups::new(CHARACTER_LAYER, WORLD_LAYER))
+                    .exclude_sensors()
+                    .predicate(&predicate),
+            )
+            .map(|(entity, toi)| {
+                // the normal is local to the ground collider
+                let rotation = transforms
+                    .get(entity)
+                    .map(|transform| transform.to_scale_rotation_translation().1)
+                    .unwrap_or_default();
+                (entity, (rotation * toi.normal1.extend(0.0)).truncate())
+            })
+            .filter(|(_, normal)| {
+                normal.angle_between(Vec2::Y).abs().to_degrees() <= character.max_slope_angle
+            });
+
+        if let Some((entity, normal)) = hit {
             if !character.grounded {
                 debug!("grounded");
             }
             character.grounded = true;
+            character.ground_entity = Some(entity);
+            character.ground_normal = normal;
+            character.slope_angle = normal.angle_between(Vec2::Y).to_degrees();
         } else {
             if character.grounded {
                 debug!("not grounded");
             }
             character.grounded = false;
+            character.ground_entity = None;
+            character.ground_normal = Vec2::Y;
+            character.slope_angle = 0.0;
+        }
+    }
+}
+
+/// Drops player characters through one-way platforms when they press down
+pub fn character_drop_through(
+    mut commands: Commands,
+    time: Res<Time>,
+    input: Res<CharacterInput2D>,
+    mut query: Query<(Entity, &Character, Option<&mut DropThrough>), With<PlayerCharacter>>,
+    one_way_platforms: Query<(), With<OneWayPlatform>>,
+) {
+    for (entity, character, drop_through) in query.iter_mut() {
+        if let Some(mut drop_through) = drop_through {
+            drop_through.remaining -= time.delta_seconds();
+            if drop_through.remaining <= 0.0 {
+                commands.entity(entity).remove::<DropThrough>();
+            }
+            continue;
+        }
+
+        let on_one_way = character
+            .ground_entity
+            .map(|ground| one_way_platforms.contains(ground))
+            .unwrap_or_default();
+
+        if on_one_way && input.direction.y < 0.0 {
+            commands.entity(entity).insert(DropThrough {
+                remaining: DROP_THROUGH_TIME,
+            });
+        }
+    }
+}
+
+/// Crouches player characters when they press down on the ground
+///
+/// Crouching shrinks the character to the bottom of its collider and sprite.
+/// Characters stay crouched while there's no room overhead to stand back up.
+/// Pressing down on one-way ground drops through it instead
+#[allow(clippy::type_complexity)]
+pub fn character_crouch(
+    mut commands: Commands,
+    input: Res<CharacterInput2D>,
+    rapier_context: Res<RapierContext>,
+    mut query: Query<
+        (
+            Entity,
+            &mut Character,
+            &mut Collider,
+            &mut Sprite,
+            &mut Transform,
+            Option<&Crouching>,
+        ),
+        With<PlayerCharacter>,
+    >,
+    one_way_platforms: Query<(), With<OneWayPlatform>>,
+) {
+    // characters can stand up through one-way platforms
+    let predicate = |entity| !one_way_platforms.contains(entity);
+
+    for (entity, mut character, mut collider, mut sprite, mut transform, crouching) in
+        query.iter_mut()
+    {
+        let on_one_way = character
+            .ground_entity
+            .map(|ground| one_way_platforms.contains(ground))
+            .unwrap_or_default();
+
+        let wants_crouch =
+            character.grounded && !character.climbing && !on_one_way && input.direction.y < 0.0;
+        let half_extents = collider_half_extents(&collider);
+
+        match crouching {
+            None if wants_crouch => {
+                debug!("crouching");
+
+                let crouched = Vec2::new(half_extents.x, half_extents.y * CROUCH_HEIGHT_FACTOR);
+
+                *collider = Collider::cuboid(crouched.x, crouched.y);
+                sprite.custom_size = Some(crouched * 2.0);
+
+                // keep the character's feet where they were
+                transform.translation.y -= half_extents.y - crouched.y;
+
+                commands.entity(entity).insert(Crouching {
+                    standing_half_extents: half_extents,
+                });
+                character.crouching = true;
+            }
+            Some(crouching) if !wants_crouch => {
+                let standing = crouching.standing_half_extents;
+                let growth = (standing.y - half_extents.y) * 2.0;
+
+                // cast a thin box up from the character's head through the space it needs
+                let shape =
+                    Collider::cuboid(half_extents.x * GROUND_CHECK_WIDTH, GROUND_CHECK_SKIN);
+                let blocked = rapier_context
+                    .cast_shape(
+                        transform.translation.truncate()
+                            + Vec2::Y * (half_extents.y - GROUND_CHECK_SKIN * 2.0),
+                        0.0,
+                        Vec2::Y,
+                        &shape,
+                        growth + GROUND_CHECK_SKIN,
+                        QueryFilter::new()
+                            .groups(InteractionGroups::new(CHARACTER_LAYER, WORLD_LAYER))
+                            .exclude_sensors()
+                            .predicate(&predicate),
+                    )
+                    .is_some();
+                if blocked {
+                    continue;
+                }
+
+                debug!("standing up");
+
+                *collider = Collider::cuboid(standing.x, standing.y);
+                sprite.custom_size = Some(standing * 2.0);
+                transform.translation.y += standing.y - half_extents.y;
+
+                commands.entity(entity).remove::<Crouching>();
+                character.crouching = false;
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Checks whether a character is touching a wall or not
+pub fn character_wall_system(
+    rapier_context: Res<RapierContext>,
+    mut query: Query<(&mut Character, &Collider, &Transform)>,
+    one_way_platforms: Query<(), With<OneWayPlatform>>,
+) {
+    // one-way platforms are never walls
+    let predicate = |entity| !one_way_platforms.contains(entity);
+
+    for (mut character, collider, transform) in query.iter_mut() {
+        let half_width = collider_half_extents(collider).x;
+
+        let position = transform.translation.truncate();
+
+        let wall_direction = [-1.0, 1.0]
+            .into_iter()
+            .find(|direction| {
+                rapier_context
+                    .cast_ray(
+                        position + Vec2::X * half_width * *direction,
+                        Vec2::X * *direction,
+                        0.1,
+                        true,
+                        QueryFilter::new()
+                            .groups(InteractionGroups::new(CHARACTER_LAYER, WORLD_LAYER))
+                            .exclude_sensors()
+                            .predicate(&predicate),
+                    )
+                    .is_some()
+            })
+            .unwrap_or_default();
+
+        if wall_direction != character.wall_direction {
+            debug!("wall direction: {}", wall_direction);
+        }
+        character.wall_direction = wall_direction;
+    }
+}
+
+/// Switches characters between the dynamic and kinematic controllers
+///
+/// The applied mode is read back from the rigidbody type and controller,
+/// so nothing happens until the character controller mode actually changes
+pub fn character_controller_mode(
+    mut commands: Commands,
+    query: Query<(
+        Entity,
+        &Character,
+        &RigidBody,
+        Option<&KinematicCharacterController>,
+    )>,
+) {
+    for (entity, character, rigid_body, controller) in query.iter() {
+        let wanted = character.rigid_body();
+        let kinematic = character.controller == CharacterControllerMode::Kinematic;
+        if *rigid_body == wanted && controller.is_some() == kinematic {
+            continue;
+        }
+
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.remove::<RigidBody>().insert(wanted);
+
+        match character.controller {
+            CharacterControllerMode::Kinematic => {
+                debug!("switching {:?} to the kinematic controller", entity);
+
+                entity_commands.insert(KinematicCharacterController::default());
+            }
+            CharacterControllerMode::Dynamic => {
+                debug!("switching {:?} to the dynamic controller", entity);
+
+                entity_commands.remove::<KinematicCharacterController>();
+            }
+        }
+    }
+}
+
+/// Moves kinematic characters
+///
+/// Rapier doesn't apply gravity, forces or impulses to kinematic bodies
+/// so those are integrated into the character velocity here.
+/// The character controller doesn't run physics hooks and can't skip single colliders,
+/// so characters rising or dropping through one-way platforms are moved here instead,
+/// with the one-way platforms filtered out
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::type_complexity)]
+pub fn kinematic_character_controller(
+    mut commands: Commands,
+    time: Res<Time>,
+    rapier_config: Res<RapierConfiguration>,
+    game_config: Res<GameConfig>,
+    mut rapier_context: ResMut<RapierContext>,
+    materials: Query<&SurfaceMaterial>,
+    one_way_platforms: Query<(), With<OneWayPlatform>>,
+    mut query: Query<(
+        Entity,
+        &Character,
+        &Collider,
+        &mut Transform,
+        &mut KinematicCharacterController,
+        &mut Velocity,
+        Option<&mut ExternalImpulse>,
+        Option<&AdditionalMassProperties>,
+        Option<&KinematicCharacterControllerOutput>,
+        Option<&DropThrough>,
+    )>,
+) {
+    let dt = time.delta_seconds();
+    let predicate = |entity| !one_way_platforms.contains(entity);
+
+    for (
+        entity,
+        character,
+        collider,
+        mut transform,
+        mut controller,
+        mut velocity,
+        impulse,
+        mass,
+        output,
+        drop_through,
+    ) in query.iter_mut()
+    {
+        let mass = additional_mass(mass);
+
+        // collision response from the last move
+        if let Some(output) = output {
+            let blocked_up = output.desired_translation.y > 0.0
+                && output.effective_translation.y < output.desired_translation.y * 0.5;
+            let landed = output.grounded && velocity.linvel.y < 0.0;
+            if blocked_up {
+                velocity.linvel.y = 0.0;
+            } else if landed {
+                // rapier doesn't bounce kinematic bodies off of bouncy surfaces
+                let restitution = character
+                    .ground_entity
+                    .and_then(|ground| materials.get(ground).ok())
+                    .map(|material| material.restitution)
+                    .unwrap_or_default();
+                velocity.linvel.y = -velocity.linvel.y * restitution;
+            }
+        }
+
+        if let Some(mut impulse) = impulse {
+            velocity.linvel += impulse.impulse / mass;
+            impulse.impulse = Vec2::ZERO;
+        }
+
+        if !character.grounded && !character.climbing {
+            velocity.linvel += rapier_config.gravity * dt;
+
+            if !character.is_swimming() {
+                velocity.linvel += game_config.character_gravity / mass * dt;
+            }
+        }
+
+        let max_slope_angle = character.max_slope_angle.to_radians();
+
+        controller.offset = CharacterLength::Absolute(0.01);
+        controller.max_slope_climb_angle = max_slope_angle;
+        controller.min_slope_slide_angle = max_slope_angle;
+        controller.autostep = Some(CharacterAutostep {
+            max_height: CharacterLength::Absolute(character.max_step_height),
+            min_width: CharacterLength::Absolute(0.1),
+            include_dynamic_bodies: false,
+        });
+        controller.snap_to_ground = Some(CharacterLength::Absolute(character.snap_to_ground));
+        controller.filter_groups = Some(InteractionGroups::new(CHARACTER_LAYER, WORLD_LAYER));
+
+        let translation = velocity.linvel * dt;
+
+        // same as the grounded check, one-way platforms only block falling characters
+        let passing_through = velocity.linvel.y > 0.0 || drop_through.is_some();
+        if !passing_through {
+            controller.translation = Some(translation);
+            continue;
+        }
+
+        let options = MoveShapeOptions {
+            offset: controller.offset,
+            max_slope_climb_angle: controller.max_slope_climb_angle,
+            min_slope_slide_angle: controller.min_slope_slide_angle,
+            autostep: controller.autostep,
+            // snapping down would pull the character back onto the platform
+            snap_to_ground: None,
+            ..Default::default()
+        };
+        let moved = rapier_context.move_shape(
+            translation,
+            collider,
+            transform.translation.truncate(),
+            transform.rotation.to_euler(EulerRot::XYZ).2,
+            mass,
+            &options,
+            QueryFilter::new()
+                .groups(InteractionGroups::new(CHARACTER_LAYER, WORLD_LAYER))
+                .exclude_sensors()
+                .exclude_collider(entity)
+                .predicate(&predicate),
+            |_| (),
+        );
+
+        controller.translation = None;
+        transform.translation += moved.effective_translation.extend(0.0);
+
+        // the controller output is stale now, so respond to the ceiling here
+        if translation.y > 0.0 && moved.effective_translation.y < translation.y * 0.5 {
+            velocity.linvel.y = 0.0;
         }
+        commands
+            .entity(entity)
+            .remove::<KinematicCharacterControllerOutput>();
     }
 }
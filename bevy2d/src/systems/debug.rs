@@ -5,8 +5,16 @@ use bevy::prelude::*;
 use bevy_egui::{egui, EguiContext};
 use bevy_inspector_egui::WorldInspectorParams;
 
+use core_lib::commands::prefab::*;
+use core_lib::components::character::*;
 use core_lib::events::debug::*;
 use core_lib::resources::debug::*;
+use core_lib::resources::input::*;
+use core_lib::resources::prefab::*;
+
+use crate::components::level::*;
+use crate::resources::level::*;
+use crate::states::GameState;
 
 /// Toggles debug on input
 ///
@@ -14,10 +22,10 @@ use core_lib::resources::debug::*;
 pub fn debug_system(
     mut inspector: ResMut<WorldInspectorParams>,
     mut debug_state: ResMut<DebugState>,
-    keyboard_input: Res<Input<KeyCode>>,
+    action_state: Res<ActionState>,
     mut debug_events: EventWriter<ToggleDebugEvent>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Grave) {
+    if action_state.just_pressed(Action::ToggleDebug) {
         debug!("toggling debug ...");
 
         debug_state.enabled = !debug_state.enabled;
@@ -49,12 +57,20 @@ fn fps(diagnostics: &Diagnostics, dt: f64) -> (f64, f64) {
 }
 
 /// Handles the debug UI
+#[allow(clippy::too_many_arguments)]
 pub fn debug_ui(
+    mut commands: Commands,
     debug_state: Res<DebugState>,
     mut context: ResMut<EguiContext>,
     mut inspector: ResMut<WorldInspectorParams>,
     time: Res<Time>,
     diagnostics: Res<Diagnostics>,
+    registry: Res<PrefabRegistry>,
+    players: Query<&Transform, With<PlayerCharacter>>,
+    mut state: ResMut<State<GameState>>,
+    mut generator: ResMut<LevelGenerator>,
+    mut selected_prefab: Local<String>,
+    mut seed: Local<u64>,
 ) {
     if !debug_state.enabled {
         return;
@@ -74,8 +90,81 @@ pub fn debug_ui(
                 inspector.enabled = !inspector.enabled;
             }
 
-            // TODO: buttons to spawn creatures would be cool
-            // but we need a way to say *where* to spawn them I think?
+            match state.current() {
+                GameState::Game => {
+                    if ui.button("Editor").clicked() {
+                        state.push(GameState::Editor).unwrap();
+                    }
+                }
+                GameState::Editor => {
+                    if ui.button("Close Editor").clicked() {
+                        state.pop().unwrap();
+                    }
+                }
+                _ => (),
+            }
+
+            ui.separator();
+
+            // spawn prefabs just above the player, for lack of a better place
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_source("prefab")
+                    .selected_text(selected_prefab.as_str())
+                    .show_ui(ui, |ui| {
+                        for name in registry.names() {
+                            ui.selectable_value(&mut *selected_prefab, name.to_owned(), name);
+                        }
+                    });
+
+                let selected = registry.get(selected_prefab.as_str()).is_some();
+                if ui
+                    .add_enabled(selected, egui::Button::new("Spawn"))
+                    .clicked()
+                {
+                    let position = players
+                        .iter()
+                        .next()
+                        .map(|transform| transform.translation.truncate() + Vec2::Y * 3.0)
+                        .unwrap_or_default();
+
+                    // cleaned up along with the level
+                    if let Some(mut entity) = commands.spawn_prefab(
+                        &registry,
+                        &selected_prefab,
+                        PrefabOverrides::at(position),
+                    ) {
+                        entity.insert(LevelEntity);
+                    }
+                }
+            });
+
+            ui.separator();
+
+            // generated levels replace the current level
+            ui.horizontal(|ui| {
+                ui.label("Seed");
+                ui.add(egui::DragValue::new(&mut *seed));
+
+                if ui.button("Generate").clicked() {
+                    generator.seed = Some(*seed);
+                }
+
+                if ui.button("Random").clicked() {
+                    *seed = rand::random();
+                    generator.seed = Some(*seed);
+                }
+
+                if ui
+                    .add_enabled(generator.seed.is_some(), egui::Button::new("Level List"))
+                    .clicked()
+                {
+                    generator.seed = None;
+                }
+            });
+
+            if let Some(seed) = generator.seed {
+                ui.label(format!("Current seed: {}", seed));
+            }
         });
     });
 }
@@ -0,0 +1,429 @@
+//! Level editor systems
+
+use bevy::asset::{AssetServerSettings, FileAssetIo};
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContext};
+use bevy_rapier2d::prelude::*;
+
+use core_lib::components::world::*;
+use core_lib::resources::prefab::*;
+
+use crate::components::level::*;
+use crate::level::*;
+use crate::resources::editor::*;
+use crate::resources::level::*;
+use crate::systems::level::*;
+use crate::{CHARACTER_LAYER, WORLD_LAYER};
+
+/// The size of newly placed platforms
+const DEFAULT_PLATFORM_SIZE: Vec2 = Vec2::new(5.0, 1.0);
+
+/// The surface material presets that can be picked for platforms
+const SURFACE_PRESETS: [&str; 4] = ["ice", "mud", "bouncy", "conveyor"];
+
+/// Converts the cursor position to a world position
+fn cursor_world_position(
+    windows: &Windows,
+    cameras: &Query<(&Camera, &GlobalTransform)>,
+) -> Option<Vec2> {
+    let window = windows.get_primary()?;
+    let cursor = window.cursor_position()?;
+    let (camera, camera_transform) = cameras.iter().next()?;
+
+    let window_size = Vec2::new(window.width(), window.height());
+    let ndc = (cursor / window_size) * 2.0 - Vec2::ONE;
+    let ndc_to_world = camera_transform.compute_matrix() * camera.projection_matrix().inverse();
+
+    Some(ndc_to_world.project_point3(ndc.extend(-1.0)).truncate())
+}
+
+/// Handles mouse and keyboard editing
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::type_complexity)]
+pub fn editor_input(
+    mut commands: Commands,
+    mut context: ResMut<EguiContext>,
+    mut editor: ResMut<EditorState>,
+    registry: Res<PrefabRegistry>,
+    windows: Res<Windows>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    cameras: Query<(&Camera, &GlobalTransform)>,
+    mut editables: Query<
+        (Entity, &mut Transform, &Sprite, Option<&mut MovingPlatform>),
+        Or<(With<LevelPlatform>, With<SpawnPoint>)>,
+    >,
+) {
+    let ctx = context.ctx_mut();
+
+    if keyboard_input.just_pressed(KeyCode::Delete) && !ctx.wants_keyboard_input() {
+        if let Some(selected) = editor.selected.take() {
+            commands.entity(selected).despawn_recursive();
+        }
+    }
+
+    // let egui have the mouse when it wants it
+    if ctx.wants_pointer_input() {
+        return;
+    }
+
+    let cursor = match cursor_world_position(&windows, &cameras) {
+        Some(cursor) => cursor,
+        None => return,
+    };
+
+    if mouse_buttons.just_pressed(MouseButton::Left) {
+        match editor.tool {
+            EditorTool::Select => {
+                let hit = editables.iter().find_map(|(entity, transform, sprite, _)| {
+                    let position = transform.translation.truncate();
+                    let half_size = sprite.custom_size.unwrap_or(Vec2::ONE) / 2.0;
+                    let offset = cursor - position;
+                    (offset.x.abs() <= half_size.x && offset.y.abs() <= half_size.y)
+                        .then(|| (entity, position - cursor))
+                });
+
+                editor.selected = hit.map(|(entity, _)| entity);
+                editor.drag_offset = hit.map(|(_, offset)| offset);
+            }
+            EditorTool::PlacePlatform => {
+                editor.selected = spawn_platform(
+                    &mut commands,
+                    &registry,
+                    &PlatformDefinition {
+                        name: "Platform".to_owned(),
+                        position: cursor,
+                        size: DEFAULT_PLATFORM_SIZE,
+                        color: Color::WHITE,
+                        collision_groups: LevelCollisionGroups::default(),
+                        ..Default::default()
+                    },
+                );
+            }
+            EditorTool::PlaceSpawn => {
+                let entity = spawn_point(
+                    &mut commands,
+                    &SpawnDefinition {
+                        prefab: editor.spawn_prefab.clone(),
+                        position: cursor,
+                        character: CharacterOverrides::default(),
+                    },
+                );
+
+                // spawn points are hidden outside of the editor
+                commands
+                    .entity(entity)
+                    .insert(Visibility { is_visible: true });
+
+                editor.selected = Some(entity);
+            }
+        }
+    }
+
+    if mouse_buttons.pressed(MouseButton::Left) {
+        if let (Some(selected), Some(offset)) = (editor.selected, editor.drag_offset) {
+            if let Ok((_, mut transform, _, moving_platform)) = editables.get_mut(selected) {
+                let position = cursor + offset;
+
+                // move the whole path along with the platform
+                if let Some(mut moving_platform) = moving_platform {
+                    moving_platform.origin += position - transform.translation.truncate();
+                }
+
+                transform.translation.x = position.x;
+                transform.translation.y = position.y;
+            }
+        }
+    }
+
+    if mouse_buttons.just_released(MouseButton::Left) {
+        editor.drag_offset = None;
+    }
+}
+
+fn layer_checkbox(ui: &mut egui::Ui, bits: &mut u32, layer: u32, label: &str) {
+    let mut set = *bits & layer != 0;
+    if ui.checkbox(&mut set, label).changed() {
+        if set {
+            *bits |= layer;
+        } else {
+            *bits &= !layer;
+        }
+    }
+}
+
+fn position_editor(ui: &mut egui::Ui, transform: &mut Transform) {
+    ui.horizontal(|ui| {
+        ui.label("Position");
+        ui.add(egui::DragValue::new(&mut transform.translation.x).speed(0.1));
+        ui.add(egui::DragValue::new(&mut transform.translation.y).speed(0.1));
+    });
+}
+
+/// Handles the editor UI
+#[allow(clippy::too_many_arguments)]
+#[allow(clippy::type_complexity)]
+pub fn editor_ui(
+    mut commands: Commands,
+    mut context: ResMut<EguiContext>,
+    mut editor: ResMut<EditorState>,
+    registry: Res<PrefabRegistry>,
+    asset_server: Res<AssetServer>,
+    asset_settings: Res<AssetServerSettings>,
+    current_level: Res<CurrentLevel>,
+    levels: Res<Assets<LevelAsset>>,
+    mut platforms: Query<
+        (
+            &Name,
+            &mut Transform,
+            &mut Sprite,
+            &mut Collider,
+            &mut CollisionGroups,
+            Option<&OneWayPlatform>,
+            Option<&mut MovingPlatform>,
+            Option<&SurfaceMaterial>,
+        ),
+        (With<LevelPlatform>, Without<SpawnPoint>),
+    >,
+    mut spawn_points: Query<(&mut SpawnPoint, &mut Transform), Without<LevelPlatform>>,
+) {
+    let level_path = asset_server
+        .get_handle_path(&current_level.handle)
+        .map(|path| path.path().to_path_buf());
+
+    egui::Window::new("Editor").show(context.ctx_mut(), |ui| {
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut editor.tool, EditorTool::Select, "Select");
+            ui.radio_value(&mut editor.tool, EditorTool::PlacePlatform, "Platform");
+            ui.radio_value(&mut editor.tool, EditorTool::PlaceSpawn, "Spawn");
+        });
+
+        if editor.tool == EditorTool::PlaceSpawn {
+            let editor = &mut *editor;
+            egui::ComboBox::from_label("Spawn prefab")
+                .selected_text(editor.spawn_prefab.as_str())
+                .show_ui(ui, |ui| {
+                    for name in registry.names() {
+                        ui.selectable_value(&mut editor.spawn_prefab, name.to_owned(), name);
+                    }
+                });
+        }
+
+        ui.separator();
+
+        if let Some(selected) = editor.selected {
+            if let Ok((
+                name,
+                mut transform,
+                mut sprite,
+                mut collider,
+                mut groups,
+                one_way,
+                moving_platform,
+                material,
+            )) = platforms.get_mut(selected)
+            {
+                ui.label(name.as_str());
+
+                // move the whole path along with the platform
+                let position = transform.translation.truncate();
+                position_editor(ui, &mut transform);
+                if let Some(mut moving_platform) = moving_platform {
+                    let delta = transform.translation.truncate() - position;
+                    if delta != Vec2::ZERO {
+                        moving_platform.origin += delta;
+                    }
+                }
+
+                let mut size = sprite.custom_size.unwrap_or(DEFAULT_PLATFORM_SIZE);
+                let resized = ui
+                    .horizontal(|ui| {
+                        ui.label("Size");
+                        let width = ui.add(
+                            egui::DragValue::new(&mut size.x)
+                                .speed(0.1)
+                                .clamp_range(0.1..=f32::MAX),
+                        );
+                        let height = ui.add(
+                            egui::DragValue::new(&mut size.y)
+                                .speed(0.1)
+                                .clamp_range(0.1..=f32::MAX),
+                        );
+                        width.changed() || height.changed()
+                    })
+                    .inner;
+                if resized {
+                    sprite.custom_size = Some(size);
+                    *collider = Collider::cuboid(size.x / 2.0, size.y / 2.0);
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Rotation");
+                    let mut rotation = transform.rotation.to_euler(EulerRot::XYZ).2.to_degrees();
+                    if ui
+                        .add(egui::DragValue::new(&mut rotation).clamp_range(-90.0..=90.0))
+                        .changed()
+                    {
+                        transform.rotation = Quat::from_rotation_z(rotation.to_radians());
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Color");
+                    let mut color = sprite.color.as_rgba_f32();
+                    if ui.color_edit_button_rgba_unmultiplied(&mut color).changed() {
+                        sprite.color = Color::rgba(color[0], color[1], color[2], color[3]);
+                    }
+                });
+
+                ui.horizontal(|ui| {
+                    ui.label("Member of");
+                    layer_checkbox(ui, &mut groups.memberships, WORLD_LAYER, "World");
+                    layer_checkbox(ui, &mut groups.memberships, CHARACTER_LAYER, "Character");
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Collides with");
+                    layer_checkbox(ui, &mut groups.filters, WORLD_LAYER, "World");
+                    layer_checkbox(ui, &mut groups.filters, CHARACTER_LAYER, "Character");
+                });
+
+                let mut is_one_way = one_way.is_some();
+                if ui.checkbox(&mut is_one_way, "One-way").changed() {
+                    if is_one_way {
+                        commands
+                            .entity(selected)
+                            .insert(OneWayPlatform)
+                            .insert(ActiveHooks::MODIFY_SOLVER_CONTACTS);
+                    } else {
+                        // rapier doesn't sync removals, so clear the hook flags explicitly
+                        commands
+                            .entity(selected)
+                            .remove::<OneWayPlatform>()
+                            .insert(ActiveHooks::empty());
+                    }
+                }
+
+                let current_surface = material
+                    .map(|material| {
+                        SURFACE_PRESETS
+                            .into_iter()
+                            .find(|name| SurfaceMaterial::preset(name).as_ref() == Some(material))
+                            .unwrap_or("custom")
+                    })
+                    .unwrap_or("none");
+                let mut surface = current_surface;
+                egui::ComboBox::from_label("Surface")
+                    .selected_text(surface)
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut surface, "none", "none");
+                        for name in SURFACE_PRESETS {
+                            ui.selectable_value(&mut surface, name, name);
+                        }
+                    });
+                if surface != current_surface {
+                    match SurfaceMaterial::preset(surface) {
+                        Some(material) => {
+                            commands
+                                .entity(selected)
+                                .insert(material.collider_friction())
+                                .insert(material.collider_restitution())
+                                .insert(material);
+                        }
+                        None => {
+                            // rapier doesn't sync removals, so reset the collider explicitly
+                            commands
+                                .entity(selected)
+                                .insert(Friction::default())
+                                .insert(Restitution::default())
+                                .remove::<SurfaceMaterial>();
+                        }
+                    }
+                }
+            } else if let Ok((mut spawn_point, mut transform)) = spawn_points.get_mut(selected) {
+                egui::ComboBox::from_label("Prefab")
+                    .selected_text(spawn_point.prefab.as_str())
+                    .show_ui(ui, |ui| {
+                        for name in registry.names() {
+                            ui.selectable_value(&mut spawn_point.prefab, name.to_owned(), name);
+                        }
+                    });
+                position_editor(ui, &mut transform);
+            }
+
+            if ui.button("Delete").clicked() {
+                commands.entity(selected).despawn_recursive();
+                editor.selected = None;
+            }
+        } else {
+            ui.label("Nothing selected");
+        }
+
+        ui.separator();
+
+        // only hand-written levels can be saved, imported levels would lose data
+        let path = level_path
+            .as_ref()
+            .filter(|path| path.to_string_lossy().ends_with(".level.ron"));
+        if ui
+            .add_enabled(path.is_some(), egui::Button::new("Save"))
+            .clicked()
+        {
+            let mut level = levels
+                .get(&current_level.handle)
+                .cloned()
+                .unwrap_or_default();
+
+            level.platforms = platforms
+                .iter()
+                .map(
+                    |(name, transform, sprite, _, groups, one_way, moving_platform, material)| {
+                        PlatformDefinition {
+                            name: name.as_str().to_owned(),
+                            // moving platforms are saved at the start of their path
+                            position: moving_platform
+                                .map(|moving_platform| moving_platform.origin)
+                                .unwrap_or_else(|| transform.translation.truncate()),
+                            size: sprite.custom_size.unwrap_or(DEFAULT_PLATFORM_SIZE),
+                            rotation: transform.rotation.to_euler(EulerRot::XYZ).2.to_degrees(),
+                            color: sprite.color,
+                            collision_groups: LevelCollisionGroups {
+                                memberships: groups.memberships,
+                                filters: groups.filters,
+                            },
+                            one_way: one_way.is_some(),
+                            path: moving_platform
+                                .map(|moving_platform| moving_platform.path.clone()),
+                            material: material.copied(),
+                        }
+                    },
+                )
+                .collect();
+
+            level.spawns = spawn_points
+                .iter()
+                .map(|(spawn_point, transform)| SpawnDefinition {
+                    prefab: spawn_point.prefab.clone(),
+                    position: transform.translation.truncate(),
+                    character: spawn_point.character,
+                })
+                .collect();
+
+            let path = FileAssetIo::get_root_path()
+                .join(&asset_settings.asset_folder)
+                .join(path.unwrap());
+
+            editor.status = match ron::ser::to_string_pretty(&level, Default::default())
+                .map_err(|err| err.to_string())
+                .and_then(|data| std::fs::write(&path, data).map_err(|err| err.to_string()))
+            {
+                Ok(_) => format!("saved {}", path.display()),
+                Err(err) => format!("failed to save {}: {}", path.display(), err),
+            };
+            info!("{}", editor.status);
+        }
+
+        if !editor.status.is_empty() {
+            ui.label(editor.status.as_str());
+        }
+    });
+}
@@ -0,0 +1,479 @@
+//! Level systems
+
+use bevy::asset::LoadState;
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use core_lib::commands::prefab::*;
+use core_lib::components::character::*;
+use core_lib::components::world::*;
+use core_lib::resources::prefab::*;
+
+use crate::components::level::*;
+use crate::events::character::*;
+use crate::level::generator::*;
+use crate::level::*;
+use crate::resources::game::*;
+use crate::resources::level::*;
+use crate::resources::world::*;
+use crate::states::game::Game;
+use crate::states::GameState;
+
+/// Despawns the current level when its asset changes
+/// so that it gets spawned again from the reloaded asset
+///
+/// The player is kept around so that it doesn't lose its state
+pub fn reload_level(
+    mut commands: Commands,
+    mut asset_events: EventReader<AssetEvent<LevelAsset>>,
+    mut current_level: ResMut<CurrentLevel>,
+    query: Query<Entity, (With<LevelEntity>, Without<PlayerCharacter>)>,
+) {
+    let mut modified = false;
+    for event in asset_events.iter() {
+        if let AssetEvent::Modified { handle } = event {
+            modified |= *handle == current_level.handle;
+        }
+    }
+
+    if !modified || !current_level.spawned {
+        return;
+    }
+
+    info!("reloading level");
+
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    current_level.spawned = false;
+}
+
+/// Moves on to the next level when the player reaches a level exit
+///
+/// Exits lead to the level's neighbour on the side the exit is on,
+/// exits without a neighbour move on to the next level in the level list.
+/// This has to run before regenerate_level so that a seeded level
+/// is replaced in the same frame that its exit is reached
+#[allow(clippy::too_many_arguments)]
+pub fn level_exit(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    rapier_context: Res<RapierContext>,
+    levels: Res<Assets<LevelAsset>>,
+    current_level: Res<CurrentLevel>,
+    mut progress: ResMut<LevelProgress>,
+    mut generator: ResMut<LevelGenerator>,
+    mut game: ResMut<Game>,
+    mut state: ResMut<State<GameState>>,
+    exits: Query<(Entity, &Transform), With<LevelExit>>,
+    players: Query<Entity, With<PlayerCharacter>>,
+    level_entities: Query<Entity, With<LevelEntity>>,
+) {
+    // the current level is already on its way out
+    if generator.is_dirty() {
+        return;
+    }
+
+    let reached = exits.iter().find(|(exit, _)| {
+        players
+            .iter()
+            .any(|player| rapier_context.intersection_pair(*exit, player) == Some(true))
+    });
+
+    let exit_transform = match reached {
+        Some((_, transform)) => transform,
+        None => return,
+    };
+
+    // seeded levels go on forever
+    if let Some(seed) = generator.seed {
+        info!("level complete, generating the next level");
+
+        generator.seed = Some(seed.wrapping_add(1));
+        game.reset_timer();
+        return;
+    }
+
+    // levels are centered on the origin
+    let direction = NeighbourDirection::from_offset(exit_transform.translation.truncate());
+    let neighbour = levels.get(&current_level.handle).and_then(|level| {
+        level
+            .neighbours
+            .iter()
+            .find(|neighbour| neighbour.direction == direction)
+    });
+
+    if let Some(neighbour) = neighbour {
+        info!("level complete, moving on to neighbour {}", neighbour.level);
+
+        for entity in level_entities.iter() {
+            commands.entity(entity).despawn_recursive();
+        }
+
+        commands.insert_resource(CurrentLevel::new(
+            asset_server.load(neighbour.level.as_str()),
+        ));
+        game.reset_timer();
+        return;
+    }
+
+    if progress.is_last_level() {
+        info!("final level complete");
+
+        state.push(GameState::Victory).unwrap();
+        return;
+    }
+
+    progress.current += 1;
+    info!("level complete, moving on to level {}", progress.current);
+
+    for entity in level_entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    commands.insert_resource(CurrentLevel::new(
+        asset_server.load(progress.current_level()),
+    ));
+    game.reset_timer();
+}
+
+/// Hits characters that touch hazards
+///
+/// Characters are only hit again once their hit-stun wears off
+pub fn hazard_contact(
+    rapier_context: Res<RapierContext>,
+    mut hit_events: EventWriter<HitEvent>,
+    hazards: Query<(Entity, &GlobalTransform), With<Hazard>>,
+    characters: Query<(Entity, &Character)>,
+) {
+    for (hazard, transform) in hazards.iter() {
+        for (entity, character) in characters.iter() {
+            if character.is_hit_stunned() {
+                continue;
+            }
+
+            if rapier_context.intersection_pair(hazard, entity) == Some(true) {
+                hit_events.send(HitEvent {
+                    character: entity,
+                    source: transform.translation().truncate(),
+                });
+            }
+        }
+    }
+}
+
+/// Returns how far the player prefab can move and jump
+pub fn player_jump_limits(
+    registry: &PrefabRegistry,
+    rapier_config: &RapierConfiguration,
+    game_config: &GameConfig,
+) -> JumpLimits {
+    let player = registry.get("player");
+    let mass = player.and_then(|prefab| prefab.mass).unwrap_or(1.0);
+    let character = player
+        .and_then(|prefab| prefab.character.clone())
+        .unwrap_or_default();
+
+    let gravity = -(rapier_config.gravity.y + game_config.character_gravity.y / mass);
+
+    JumpLimits {
+        speed: character.speed,
+        jump_velocity: character.jump_force.y / mass,
+        gravity: gravity.max(f32::EPSILON),
+    }
+}
+
+/// Replaces the current level when the generator seed changes
+#[allow(clippy::too_many_arguments)]
+pub fn regenerate_level(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    registry: Res<PrefabRegistry>,
+    rapier_config: Res<RapierConfiguration>,
+    game_config: Res<GameConfig>,
+    windows: Res<Windows>,
+    progress: Res<LevelProgress>,
+    mut generator: ResMut<LevelGenerator>,
+    mut levels: ResMut<Assets<LevelAsset>>,
+    level_entities: Query<Entity, With<LevelEntity>>,
+) {
+    if !generator.is_dirty() || !registry.is_ready() {
+        return;
+    }
+
+    for entity in level_entities.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let handle = match generator.seed {
+        Some(seed) => {
+            info!("generating level from seed {}", seed);
+
+            // generate inside the default bounds, not whatever the last level used
+            let bounds = WorldBounds2D::from_window(windows.get_primary().unwrap());
+            let limits = player_jump_limits(&registry, &rapier_config, &game_config);
+            levels.add(generate_level(seed, bounds, limits, &generator.settings))
+        }
+        None => {
+            info!(
+                "loading level {}: {}",
+                progress.current,
+                progress.current_level()
+            );
+            asset_server.load(progress.current_level())
+        }
+    };
+
+    commands.insert_resource(CurrentLevel::new(handle));
+    generator.generated = generator.seed;
+}
+
+/// Spawns the current level once it's finished loading
+pub fn spawn_level(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    windows: Res<Windows>,
+    levels: Res<Assets<LevelAsset>>,
+    registry: Res<PrefabRegistry>,
+    mut current_level: ResMut<CurrentLevel>,
+    players: Query<(), With<PlayerCharacter>>,
+) {
+    if current_level.spawned || !registry.is_ready() {
+        return;
+    }
+
+    let level = match levels.get(&current_level.handle) {
+        Some(level) => level,
+        None => {
+            if asset_server.get_load_state(&current_level.handle) == LoadState::Failed {
+                error!("failed to load level");
+                current_level.spawned = true;
+            }
+            return;
+        }
+    };
+
+    info!("spawning level '{}'", level.name);
+
+    // levels without bounds don't keep the previous level's bounds
+    let bounds = level
+        .bounds
+        .unwrap_or_else(|| WorldBounds2D::from_window(windows.get_primary().unwrap()));
+    info!("level world bounds: {:?}", bounds);
+    commands.insert_resource(bounds);
+
+    for platform in level.platforms.iter() {
+        spawn_platform(&mut commands, &registry, platform);
+    }
+
+    for trigger in level.triggers.iter() {
+        spawn_trigger(&mut commands, &registry, trigger);
+    }
+
+    for chunk in level.tile_chunks.iter() {
+        spawn_tile_chunk(&mut commands, chunk);
+    }
+
+    for spawn in level.spawns.iter() {
+        spawn_point(&mut commands, spawn);
+
+        // don't replace a player that survived a reload
+        let player = registry
+            .get(&spawn.prefab)
+            .map(|prefab| prefab.player)
+            .unwrap_or_default();
+        if player && !players.is_empty() {
+            continue;
+        }
+
+        spawn_character(&mut commands, &registry, spawn);
+    }
+
+    current_level.spawned = true;
+}
+
+/// Moves moving platforms along their paths
+pub fn moving_platforms(time: Res<Time>, mut query: Query<(&mut MovingPlatform, &mut Transform)>) {
+    let dt = time.delta_seconds();
+    if dt <= 0.0 {
+        return;
+    }
+
+    for (mut platform, mut transform) in query.iter_mut() {
+        let position = platform.advance(dt);
+
+        platform.velocity = (position - transform.translation.truncate()) / dt;
+
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+    }
+}
+
+/// Spawns a level platform
+pub fn spawn_platform(
+    commands: &mut Commands,
+    registry: &PrefabRegistry,
+    platform: &PlatformDefinition,
+) -> Option<Entity> {
+    let mut entity = commands.spawn_prefab(
+        registry,
+        "platform",
+        PrefabOverrides {
+            name: Some(platform.name.clone()),
+            size: Some(platform.size),
+            color: Some(platform.color),
+            transform: Transform::from_translation(platform.position.extend(0.0))
+                .with_rotation(Quat::from_rotation_z(platform.rotation.to_radians())),
+            ..Default::default()
+        },
+    )?;
+
+    entity
+        .insert(CollisionGroups::from(platform.collision_groups))
+        .insert(LevelPlatform)
+        .insert(LevelEntity);
+
+    if let Some(path) = &platform.path {
+        entity
+            .insert(RigidBody::KinematicPositionBased)
+            .insert(MovingPlatform::new(platform.position, path.clone()));
+    }
+
+    if platform.one_way {
+        entity
+            .insert(OneWayPlatform)
+            .insert(ActiveHooks::MODIFY_SOLVER_CONTACTS);
+    }
+
+    if let Some(material) = platform.material {
+        entity
+            .insert(material.collider_friction())
+            .insert(material.collider_restitution())
+            .insert(material);
+    }
+
+    Some(entity.id())
+}
+
+/// Spawns a (hidden) level spawn point marker
+pub fn spawn_point(commands: &mut Commands, spawn: &SpawnDefinition) -> Entity {
+    commands
+        .spawn_bundle(SpriteBundle {
+            sprite: Sprite {
+                color: Color::rgba(1.0, 0.0, 1.0, 0.5),
+                custom_size: Some(Vec2::new(1.0, 2.0)),
+                ..Default::default()
+            },
+            transform: Transform::from_translation(spawn.position.extend(1.0)),
+            visibility: Visibility { is_visible: false },
+            ..Default::default()
+        })
+        .insert(SpawnPoint {
+            prefab: spawn.prefab.clone(),
+            character: spawn.character,
+        })
+        .insert(LevelEntity)
+        .insert(Name::new(format!("Spawn Point ({})", spawn.prefab)))
+        .id()
+}
+
+fn spawn_trigger(commands: &mut Commands, registry: &PrefabRegistry, trigger: &TriggerDefinition) {
+    if let Some(mut entity) = commands.spawn_prefab(
+        registry,
+        "trigger",
+        PrefabOverrides {
+            name: Some(format!("Trigger ({})", trigger.name)),
+            size: Some(trigger.size),
+            ..PrefabOverrides::at(trigger.position)
+        },
+    ) {
+        entity
+            .insert(LevelTrigger {
+                name: trigger.name.clone(),
+            })
+            .insert(LevelEntity);
+
+        match trigger.kind {
+            TriggerKind::Generic => (),
+            TriggerKind::Exit => {
+                entity.insert(LevelExit);
+            }
+            TriggerKind::Ladder => {
+                entity.insert(Climbable);
+            }
+            TriggerKind::Water => {
+                entity.insert(Water::default());
+                trigger_sprite(&mut entity, trigger.size, Color::rgba(0.0, 0.3, 1.0, 0.4));
+            }
+            TriggerKind::Hazard => {
+                entity.insert(Hazard);
+                trigger_sprite(&mut entity, trigger.size, Color::rgba(1.0, 0.0, 0.0, 0.6));
+            }
+        }
+    }
+}
+
+/// Makes a trigger volume visible
+fn trigger_sprite(entity: &mut EntityCommands, size: Vec2, color: Color) {
+    entity
+        .insert_bundle(VisibilityBundle::default())
+        .with_children(|parent| {
+            parent.spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(size),
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(Vec3::Z),
+                ..Default::default()
+            });
+        });
+}
+
+fn spawn_tile_chunk(commands: &mut Commands, chunk: &TileChunk) {
+    commands
+        .spawn_bundle(SpatialBundle::from_transform(Transform::from_translation(
+            chunk.position,
+        )))
+        .insert(LevelEntity)
+        .insert(Name::new("Tile Chunk"))
+        .with_children(|parent| {
+            for tile in chunk.tiles.iter() {
+                parent.spawn_bundle(SpriteSheetBundle {
+                    sprite: TextureAtlasSprite {
+                        index: tile.index,
+                        custom_size: Some(chunk.tile_size),
+                        ..Default::default()
+                    },
+                    texture_atlas: chunk.atlas.clone(),
+                    transform: Transform::from_translation(tile.position.extend(0.0)),
+                    ..Default::default()
+                });
+            }
+        });
+}
+
+fn spawn_character(commands: &mut Commands, registry: &PrefabRegistry, spawn: &SpawnDefinition) {
+    let character = registry
+        .get(&spawn.prefab)
+        .and_then(|prefab| prefab.character.clone());
+
+    if let Some(mut entity) =
+        commands.spawn_prefab(registry, &spawn.prefab, PrefabOverrides::at(spawn.position))
+    {
+        entity.insert(LevelEntity);
+
+        if let Some(mut character) = character {
+            let overrides = &spawn.character;
+            character.speed = overrides.speed.unwrap_or(character.speed);
+            character.air_control_factor = overrides
+                .air_control_factor
+                .unwrap_or(character.air_control_factor);
+            character.jump_force = overrides.jump_force.unwrap_or(character.jump_force);
+
+            entity.insert(character);
+        }
+    }
+}
@@ -3,10 +3,15 @@
 use bevy::prelude::*;
 use bevy_rapier2d::prelude::*;
 
+pub mod ability;
 pub mod character;
 pub mod debug;
+pub mod editor;
+pub mod level;
+pub mod world;
 
 use core_lib::components::character::*;
+use core_lib::resources::input::*;
 
 use crate::events::PauseEvent;
 use crate::states::GameState;
@@ -21,17 +26,24 @@ pub enum Systems {
 
     CharacterInput,
     CharacterJumpInput,
+    CharacterDashInput,
+    CharacterMotion,
+
+    RegenerateLevel,
+    ReloadLevel,
+
+    ScreenWrap,
 }
 
 /// Checks for pause input
 pub fn pause_input(
-    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut action_state: ResMut<ActionState>,
     mut pause_events: EventWriter<PauseEvent>,
 ) {
-    if keyboard_input.just_pressed(KeyCode::Escape) {
+    if action_state.just_pressed(Action::Pause) {
         info!("sending pause event");
 
-        keyboard_input.clear_just_pressed(KeyCode::Escape);
+        action_state.clear_just_pressed(Action::Pause);
 
         pause_events.send(PauseEvent);
     }
@@ -65,18 +77,18 @@ pub fn unpause(
     mut commands: Commands,
     mut state: ResMut<State<GameState>>,
     event_reader: EventReader<PauseEvent>,
-    query: Query<Entity, With<PlayerCharacter>>,
+    query: Query<(Entity, &Character), With<PlayerCharacter>>,
 ) {
     if !event_reader.is_empty() {
         info!("unpause");
 
         event_reader.clear();
 
-        for entity in query.iter() {
+        for (entity, character) in query.iter() {
             commands
                 .entity(entity)
                 .remove::<RigidBody>()
-                .insert(RigidBody::Dynamic);
+                .insert(character.rigid_body());
         }
 
         state.pop().unwrap();
@@ -0,0 +1,73 @@
+//! World systems
+
+use bevy::prelude::*;
+
+use core_lib::components::world::*;
+
+use crate::components::world::*;
+use crate::resources::world::*;
+
+/// Gives new wrapping entities a (hidden) ghost sprite
+pub fn spawn_wrap_ghosts(mut commands: Commands, query: Query<Entity, Added<ScreenWrap>>) {
+    for entity in query.iter() {
+        commands.entity(entity).with_children(|parent| {
+            parent
+                .spawn_bundle(SpriteBundle {
+                    visibility: Visibility { is_visible: false },
+                    ..Default::default()
+                })
+                .insert(WrapGhost)
+                .insert(Name::new("Wrap Ghost"));
+        });
+    }
+}
+
+/// Wraps entities that leave the horizontal edges of the world
+/// around to the other side
+///
+/// Entities wrap once their center crosses the edge,
+/// their ghost covers the part that's still across the edge
+pub fn screen_wrap(bounds: Res<WorldBounds2D>, mut query: Query<&mut Transform, With<ScreenWrap>>) {
+    for mut transform in query.iter_mut() {
+        if transform.translation.x < bounds.min.x {
+            transform.translation.x += bounds.width();
+        } else if transform.translation.x > bounds.max.x {
+            transform.translation.x -= bounds.width();
+        }
+    }
+}
+
+/// Shows the wrap ghost on the opposite edge while its entity straddles the edge
+#[allow(clippy::type_complexity)]
+pub fn update_wrap_ghosts(
+    bounds: Res<WorldBounds2D>,
+    wrapping: Query<(&Transform, &Sprite, &Children), (With<ScreenWrap>, Without<WrapGhost>)>,
+    mut ghosts: Query<(&mut Transform, &mut Sprite, &mut Visibility), With<WrapGhost>>,
+) {
+    for (transform, sprite, children) in wrapping.iter() {
+        let half_width = sprite.custom_size.map(|size| size.x).unwrap_or(1.0) / 2.0;
+        let x = transform.translation.x;
+
+        // ghosts are children, so they're positioned relative to the entity
+        let offset = if x - half_width < bounds.min.x {
+            Some(bounds.width())
+        } else if x + half_width > bounds.max.x {
+            Some(-bounds.width())
+        } else {
+            None
+        };
+
+        for child in children.iter() {
+            if let Ok((mut ghost_transform, mut ghost_sprite, mut visibility)) =
+                ghosts.get_mut(*child)
+            {
+                visibility.is_visible = offset.is_some();
+                ghost_transform.translation.x = offset.unwrap_or_default();
+
+                ghost_sprite.color = sprite.color;
+                ghost_sprite.custom_size = sprite.custom_size;
+                ghost_sprite.flip_x = sprite.flip_x;
+            }
+        }
+    }
+}
@@ -0,0 +1,3 @@
+//! ECS command extensions
+
+pub mod prefab;
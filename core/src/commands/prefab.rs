@@ -0,0 +1,157 @@
+//! Prefab commands
+
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::components::character::*;
+use crate::components::world::*;
+use crate::resources::prefab::*;
+
+/// Per-spawn prefab overrides
+#[derive(Debug, Default, Clone)]
+pub struct PrefabOverrides {
+    /// Where to spawn the prefab
+    pub transform: Transform,
+
+    /// Overrides the entity name
+    pub name: Option<String>,
+
+    /// Overrides the sprite and collider size
+    pub size: Option<Vec2>,
+
+    /// Overrides the sprite color
+    pub color: Option<Color>,
+}
+
+impl PrefabOverrides {
+    /// Overrides that only set the spawn position
+    pub fn at(position: Vec2) -> Self {
+        Self {
+            transform: Transform::from_translation(position.extend(0.0)),
+            ..Default::default()
+        }
+    }
+}
+
+/// Prefab spawning extension for Commands
+pub trait SpawnPrefabExt<'w, 's> {
+    /// Spawns the named prefab
+    ///
+    /// Returns None if the prefab doesn't exist
+    fn spawn_prefab<'a>(
+        &'a mut self,
+        registry: &PrefabRegistry,
+        name: &str,
+        overrides: PrefabOverrides,
+    ) -> Option<EntityCommands<'w, 's, 'a>>;
+}
+
+impl<'w, 's> SpawnPrefabExt<'w, 's> for Commands<'w, 's> {
+    fn spawn_prefab<'a>(
+        &'a mut self,
+        registry: &PrefabRegistry,
+        name: &str,
+        overrides: PrefabOverrides,
+    ) -> Option<EntityCommands<'w, 's, 'a>> {
+        let prefab = match registry.get(name) {
+            Some(prefab) => prefab,
+            None => {
+                warn!("no such prefab '{}'", name);
+                return None;
+            }
+        };
+
+        let mut entity = match &prefab.sprite {
+            Some(sprite) => self.spawn_bundle(SpriteBundle {
+                sprite: Sprite {
+                    color: overrides.color.unwrap_or(sprite.color),
+                    custom_size: Some(overrides.size.unwrap_or(sprite.size)),
+                    ..Default::default()
+                },
+                transform: overrides.transform,
+                ..Default::default()
+            }),
+            None => self.spawn_bundle(TransformBundle::from_transform(overrides.transform)),
+        };
+
+        // rigidbody
+        if let Some(rigid_body) = prefab.rigid_body {
+            entity.insert(RigidBody::from(rigid_body));
+        }
+
+        if prefab.lock_rotation {
+            entity.insert(LockedAxes::ROTATION_LOCKED);
+        }
+
+        if let Some(mass) = prefab.mass {
+            entity.insert(AdditionalMassProperties::MassProperties(MassProperties {
+                local_center_of_mass: Vec2::ZERO,
+                mass,
+                principal_inertia: 0.0,
+            }));
+        }
+
+        if prefab.velocity {
+            entity.insert(Velocity::default());
+        }
+
+        // collider
+        if let Some(collider) = &prefab.collider {
+            entity.insert(collider.collider(overrides.size));
+        }
+
+        if prefab.sensor {
+            entity.insert(Sensor);
+        }
+
+        if let Some(groups) = prefab.collision_groups {
+            entity.insert(CollisionGroups::new(groups.memberships, groups.filters));
+        }
+
+        // character
+        if let Some(character) = &prefab.character {
+            entity.insert(character.clone());
+        }
+
+        if prefab.player {
+            entity.insert(PlayerCharacter::default());
+        }
+
+        if prefab.non_player {
+            entity.insert(NonPlayerCharacter::default());
+        }
+
+        if prefab.screen_wrap {
+            entity.insert(ScreenWrap);
+        }
+
+        // abilities
+        if let Some(multi_jump) = &prefab.multi_jump {
+            entity.insert(multi_jump.clone());
+        }
+
+        if let Some(dash) = &prefab.dash {
+            entity.insert(dash.clone());
+        }
+
+        if let Some(pickup) = &prefab.pickup {
+            entity.insert(pickup.clone());
+        }
+
+        // forces
+        if prefab.external_forces {
+            entity
+                .insert(ExternalForce::default())
+                .insert(ExternalImpulse::default());
+        }
+
+        let name = overrides
+            .name
+            .or_else(|| prefab.name.clone())
+            .unwrap_or_else(|| name.to_owned());
+        entity.insert(Name::new(name));
+
+        Some(entity)
+    }
+}
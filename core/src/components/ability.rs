@@ -0,0 +1,133 @@
+//! Character ability components
+
+use bevy::prelude::*;
+use bevy_inspector_egui::Inspectable;
+use derivative::Derivative;
+use serde::{Deserialize, Serialize};
+
+/// Lets a character jump again while in the air
+#[derive(Debug, Derivative, Clone, Component, Inspectable, Deserialize, Serialize)]
+#[derivative(Default)]
+#[serde(default)]
+pub struct MultiJump {
+    /// How many extra jumps the character gets before landing
+    #[derivative(Default(value = "1"))]
+    pub air_jumps: usize,
+
+    /// How many air jumps are left
+    #[serde(skip)]
+    pub remaining: usize,
+}
+
+impl MultiJump {
+    /// Gives back all of the air jumps
+    pub fn reset(&mut self) {
+        self.remaining = self.air_jumps;
+    }
+
+    /// Uses up an air jump if there are any left
+    pub fn try_jump(&mut self) -> bool {
+        if self.remaining == 0 {
+            return false;
+        }
+
+        self.remaining -= 1;
+        true
+    }
+}
+
+/// Lets a character dash in a direction
+#[derive(Debug, Derivative, Clone, Component, Inspectable, Deserialize, Serialize)]
+#[derivative(Default)]
+#[serde(default)]
+pub struct Dash {
+    /// How fast the character moves while dashing
+    #[derivative(Default(value = "30.0"))]
+    pub speed: f32,
+
+    /// How long the dash lasts (seconds)
+    #[derivative(Default(value = "0.15"))]
+    pub duration: f32,
+
+    /// How long before the character can dash again (seconds)
+    #[derivative(Default(value = "0.75"))]
+    pub cooldown: f32,
+
+    /// How long the character is invulnerable after starting a dash (seconds)
+    #[derivative(Default(value = "0.2"))]
+    pub invulnerability: f32,
+
+    /// The current dash direction
+    #[serde(skip)]
+    #[inspectable(ignore)]
+    direction: Vec2,
+
+    /// How long the current dash has left
+    #[serde(skip)]
+    #[inspectable(ignore)]
+    remaining: f32,
+
+    /// How long until the character can dash again
+    #[serde(skip)]
+    #[inspectable(ignore)]
+    cooldown_remaining: f32,
+
+    /// How long the character has left being invulnerable
+    #[serde(skip)]
+    #[inspectable(ignore)]
+    invulnerability_remaining: f32,
+}
+
+impl Dash {
+    /// Starts dashing in the given direction if the dash is off cooldown
+    pub fn try_start(&mut self, direction: Vec2) -> bool {
+        if self.cooldown_remaining > 0.0 || direction == Vec2::ZERO {
+            return false;
+        }
+
+        self.direction = direction.normalize();
+        self.remaining = self.duration;
+        self.cooldown_remaining = self.cooldown;
+        self.invulnerability_remaining = self.invulnerability;
+        true
+    }
+
+    /// Advances the dash timers
+    pub fn update(&mut self, dt: f32) {
+        self.remaining = (self.remaining - dt).max(0.0);
+        self.cooldown_remaining = (self.cooldown_remaining - dt).max(0.0);
+        self.invulnerability_remaining = (self.invulnerability_remaining - dt).max(0.0);
+    }
+
+    /// Is the character dashing?
+    pub fn is_dashing(&self) -> bool {
+        self.remaining > 0.0
+    }
+
+    /// Is the character invulnerable from dashing?
+    pub fn is_invulnerable(&self) -> bool {
+        self.invulnerability_remaining > 0.0
+    }
+
+    /// The velocity the character should have while dashing
+    pub fn velocity(&self) -> Vec2 {
+        self.direction * self.speed
+    }
+}
+
+/// Characters with this can't be hurt
+#[derive(Default, Component, Inspectable)]
+pub struct Invulnerable;
+
+/// Grants (or removes) abilities to player characters that touch it
+#[derive(Debug, Clone, Component, Deserialize, Serialize)]
+pub enum AbilityPickup {
+    /// Grants air jumps
+    MultiJump(MultiJump),
+
+    /// Grants a dash
+    Dash(Dash),
+
+    /// Removes all abilities
+    RemoveAll,
+}
@@ -2,21 +2,360 @@
 
 use bevy::prelude::*;
 use bevy_inspector_egui::Inspectable;
+use bevy_rapier2d::prelude::*;
+use derivative::Derivative;
+use serde::{Deserialize, Serialize};
+
+use super::world::SurfaceMaterial;
+
+/// How a character is moved
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Inspectable, Deserialize, Serialize)]
+pub enum CharacterControllerMode {
+    /// A dynamic rigidbody pushed around by forces
+    Dynamic,
+
+    /// A kinematic rigidbody moved by a character controller
+    Kinematic,
+}
+
+impl Default for CharacterControllerMode {
+    fn default() -> Self {
+        Self::Dynamic
+    }
+}
 
 /// A... character? I dunno
-#[derive(Default, Component, Inspectable)]
+#[derive(Debug, Derivative, Clone, Component, Inspectable, Deserialize, Serialize)]
+#[derivative(Default)]
+#[serde(default)]
 pub struct Character {
+    /// How the character is moved
+    pub controller: CharacterControllerMode,
+
     /// How fast the character moves (physics independent)
     pub speed: f32,
 
     /// What % of the character speed should be allowed for air control
     pub air_control_factor: f32,
 
+    /// How quickly the character speeds up on the ground
+    #[derivative(Default(value = "80.0"))]
+    pub ground_acceleration: f32,
+
+    /// How quickly the character slows down on the ground without input
+    #[derivative(Default(value = "100.0"))]
+    pub ground_deceleration: f32,
+
+    /// How quickly the character turns around on the ground
+    #[derivative(Default(value = "150.0"))]
+    pub ground_turn_acceleration: f32,
+
+    /// How quickly the character speeds up in the air
+    #[derivative(Default(value = "40.0"))]
+    pub air_acceleration: f32,
+
+    /// How quickly the character slows down in the air without input
+    #[derivative(Default(value = "20.0"))]
+    pub air_deceleration: f32,
+
+    /// How quickly the character turns around in the air
+    #[derivative(Default(value = "60.0"))]
+    pub air_turn_acceleration: f32,
+
     /// The character jump force
     pub jump_force: Vec2,
 
+    /// How long after leaving the ground the character can still jump (seconds)
+    #[derivative(Default(value = "0.1"))]
+    pub coyote_time: f32,
+
+    /// How long before landing a jump can be pressed and still happen (seconds)
+    #[derivative(Default(value = "0.1"))]
+    pub jump_buffer_time: f32,
+
+    /// How much upward velocity is kept when the jump is released early
+    #[derivative(Default(value = "0.5"))]
+    pub jump_cut_factor: f32,
+
+    /// The fastest the character can fall while sliding down a wall
+    #[derivative(Default(value = "2.0"))]
+    pub wall_slide_speed: f32,
+
+    /// The wall jump force, x is applied away from the wall
+    pub wall_jump_force: Vec2,
+
+    /// How long movement input is ignored after a wall jump (seconds)
+    #[derivative(Default(value = "0.15"))]
+    pub wall_jump_lockout: f32,
+
+    /// The steepest slope the character can walk on (degrees)
+    #[derivative(Default(value = "45.0"))]
+    pub max_slope_angle: f32,
+
+    /// What % of the character speed is allowed while crouching
+    #[derivative(Default(value = "0.5"))]
+    pub crouch_speed_factor: f32,
+
+    /// What % of the character speed is allowed while swimming
+    #[derivative(Default(value = "0.6"))]
+    pub swim_speed_factor: f32,
+
+    /// The swim stroke force, replaces jumping while swimming
+    pub swim_stroke_force: Vec2,
+
+    /// The knockback force when the character gets hit, x is applied away from the hit
+    pub knockback_force: Vec2,
+
+    /// How long movement input is ignored after getting hit (seconds)
+    #[derivative(Default(value = "0.3"))]
+    pub hit_stun_time: f32,
+
+    /// How fast the character climbs ladders
+    #[derivative(Default(value = "5.0"))]
+    pub climb_speed: f32,
+
+    /// The tallest step the character can climb (kinematic controller only)
+    #[derivative(Default(value = "0.3"))]
+    pub max_step_height: f32,
+
+    /// How far the character snaps down to the ground (kinematic controller only)
+    #[derivative(Default(value = "0.2"))]
+    pub snap_to_ground: f32,
+
     /// Is the character on the ground?
+    #[serde(skip)]
     pub grounded: bool,
+
+    /// The ground surface normal
+    #[serde(skip)]
+    #[derivative(Default(value = "Vec2::Y"))]
+    pub ground_normal: Vec2,
+
+    /// The ground slope angle (degrees)
+    #[serde(skip)]
+    pub slope_angle: f32,
+
+    /// Is the character climbing?
+    #[serde(skip)]
+    pub climbing: bool,
+
+    /// Is the character crouching?
+    #[serde(skip)]
+    pub crouching: bool,
+
+    /// The water the character is swimming in
+    #[serde(skip)]
+    #[inspectable(ignore)]
+    pub water_entity: Option<Entity>,
+
+    /// The entity the character is standing on
+    #[serde(skip)]
+    #[inspectable(ignore)]
+    pub ground_entity: Option<Entity>,
+
+    /// Which side the character is touching a wall on, -1 (left), 1 (right) or 0 (none)
+    #[serde(skip)]
+    pub wall_direction: f32,
+
+    /// How much wall jump lockout is left
+    #[serde(skip)]
+    #[inspectable(ignore)]
+    wall_jump_lockout_remaining: f32,
+
+    /// Is the character rising from a jump?
+    #[serde(skip)]
+    #[inspectable(ignore)]
+    jumping: bool,
+
+    /// How much coyote time is left
+    #[serde(skip)]
+    #[inspectable(ignore)]
+    coyote_remaining: f32,
+
+    /// How long the buffered jump has left, if there is one
+    #[serde(skip)]
+    #[inspectable(ignore)]
+    jump_buffer_remaining: Option<f32>,
+
+    /// How much hit-stun is left
+    #[serde(skip)]
+    #[inspectable(ignore)]
+    hit_stun_remaining: f32,
+}
+
+impl Character {
+    /// Returns the rigidbody type for the character controller
+    pub fn rigid_body(&self) -> RigidBody {
+        match self.controller {
+            CharacterControllerMode::Dynamic => RigidBody::Dynamic,
+            CharacterControllerMode::Kinematic => RigidBody::KinematicPositionBased,
+        }
+    }
+
+    /// Buffers a jump, it happens the next time the character is able to jump
+    pub fn request_jump(&mut self) {
+        self.jump_buffer_remaining = Some(self.jump_buffer_time);
+    }
+
+    /// Returns the fastest the character can move under its own power
+    pub fn max_speed(&self) -> f32 {
+        let speed = if self.is_swimming() {
+            self.speed * self.swim_speed_factor
+        } else if self.grounded {
+            self.speed
+        } else {
+            self.speed * self.air_control_factor
+        };
+
+        if self.crouching {
+            speed * self.crouch_speed_factor
+        } else {
+            speed
+        }
+    }
+
+    /// Steers a speed towards the input direction (-1 to 1)
+    /// using the ground or air acceleration curves
+    ///
+    /// The surface the character is on scales its max speed and acceleration
+    pub fn steer(&self, current: f32, direction: f32, surface: &SurfaceMaterial, dt: f32) -> f32 {
+        let target = direction * self.max_speed() * surface.speed_factor;
+
+        let (acceleration, deceleration, turn_acceleration) = if self.grounded {
+            (
+                self.ground_acceleration,
+                self.ground_deceleration,
+                self.ground_turn_acceleration,
+            )
+        } else {
+            (
+                self.air_acceleration,
+                self.air_deceleration,
+                self.air_turn_acceleration,
+            )
+        };
+
+        let rate = if current * target < 0.0 {
+            turn_acceleration
+        } else if direction == 0.0 || current.abs() > target.abs() {
+            deceleration
+        } else {
+            acceleration
+        };
+
+        let max_delta = rate * surface.traction * dt;
+        current + (target - current).clamp(-max_delta, max_delta)
+    }
+
+    /// Returns the direction to walk along the ground
+    pub fn ground_tangent(&self) -> Vec2 {
+        Vec2::new(self.ground_normal.y, -self.ground_normal.x)
+    }
+
+    /// Is the character swimming?
+    pub fn is_swimming(&self) -> bool {
+        self.water_entity.is_some()
+    }
+
+    /// Is the character sliding down a wall?
+    pub fn is_wall_sliding(&self) -> bool {
+        !self.grounded && !self.climbing && self.wall_direction != 0.0
+    }
+
+    /// Is movement input locked out by a wall jump?
+    pub fn is_wall_jump_locked(&self) -> bool {
+        self.wall_jump_lockout_remaining > 0.0
+    }
+
+    /// Is movement input locked out by getting hit?
+    pub fn is_hit_stunned(&self) -> bool {
+        self.hit_stun_remaining > 0.0
+    }
+
+    /// Hits the character from the given direction (-1 from the left, 1 from the right),
+    /// cancelling any jump. Hit-stunned characters let go of ladders when climbing is next updated
+    ///
+    /// Returns the knockback impulse
+    pub fn hit(&mut self, direction: f32) -> Vec2 {
+        self.hit_stun_remaining = self.hit_stun_time;
+        self.jumping = false;
+        self.jump_buffer_remaining = None;
+
+        Vec2::new(-direction * self.knockback_force.x, self.knockback_force.y)
+    }
+
+    /// Advances the hit-stun timer
+    pub fn update_hit_stun(&mut self, dt: f32) {
+        self.hit_stun_remaining = (self.hit_stun_remaining - dt).max(0.0);
+    }
+
+    /// Advances the jump timers
+    ///
+    /// Returns the jump impulse if the character should jump this frame
+    pub fn update_jump(&mut self, dt: f32) -> Option<Vec2> {
+        // jumping off of ladders works the same as jumping off the ground
+        if self.grounded || self.climbing {
+            self.coyote_remaining = self.coyote_time;
+        }
+
+        self.wall_jump_lockout_remaining = (self.wall_jump_lockout_remaining - dt).max(0.0);
+
+        if self.jump_buffer_remaining.is_some() {
+            let impulse = if self.coyote_remaining > 0.0 {
+                Some(self.jump_force)
+            } else if self.is_wall_sliding() {
+                self.wall_jump_lockout_remaining = self.wall_jump_lockout;
+                Some(Vec2::new(
+                    -self.wall_direction * self.wall_jump_force.x,
+                    self.wall_jump_force.y,
+                ))
+            } else {
+                None
+            };
+
+            if impulse.is_some() {
+                self.climbing = false;
+                self.coyote_remaining = 0.0;
+                self.jump_buffer_remaining = None;
+                self.jumping = true;
+                return impulse;
+            }
+        }
+
+        if !self.grounded {
+            self.coyote_remaining = (self.coyote_remaining - dt).max(0.0);
+        }
+
+        self.jump_buffer_remaining = self
+            .jump_buffer_remaining
+            .map(|remaining| remaining - dt)
+            .filter(|remaining| *remaining >= 0.0);
+
+        None
+    }
+
+    /// Jumps without checking if the character is able to, for air jumps
+    ///
+    /// Returns the jump impulse
+    pub fn force_jump(&mut self) -> Vec2 {
+        self.climbing = false;
+        self.coyote_remaining = 0.0;
+        self.jump_buffer_remaining = None;
+        self.jumping = true;
+        self.jump_force
+    }
+
+    /// Is the character rising from a jump?
+    pub fn is_jumping(&self) -> bool {
+        self.jumping
+    }
+
+    /// Ends the current jump
+    ///
+    /// Returns the factor to scale upward velocity by if the character was jumping
+    pub fn release_jump(&mut self) -> Option<f32> {
+        std::mem::take(&mut self.jumping).then(|| self.jump_cut_factor)
+    }
 }
 
 /// A... player character? sure
@@ -1,3 +1,5 @@
 //! ECS components
 
+pub mod ability;
 pub mod character;
+pub mod world;
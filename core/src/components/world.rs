@@ -0,0 +1,109 @@
+//! World components
+
+use bevy::prelude::*;
+use bevy_inspector_egui::Inspectable;
+use bevy_rapier2d::prelude::*;
+use derivative::Derivative;
+use serde::{Deserialize, Serialize};
+
+/// Wraps the entity around the horizontal edges of the world
+#[derive(Default, Component, Inspectable)]
+pub struct ScreenWrap;
+
+/// How a world collider's surface affects whatever stands on it
+#[derive(
+    Debug, Derivative, Copy, Clone, PartialEq, Component, Inspectable, Deserialize, Serialize,
+)]
+#[derivative(Default)]
+#[serde(default)]
+pub struct SurfaceMaterial {
+    /// Collider friction coefficient
+    #[derivative(Default(value = "0.5"))]
+    pub friction: f32,
+
+    /// Collider restitution coefficient, how bouncy the surface is
+    pub restitution: f32,
+
+    /// Scales the max speed of characters on the surface
+    #[derivative(Default(value = "1.0"))]
+    pub speed_factor: f32,
+
+    /// Scales how quickly characters on the surface speed up, slow down and turn
+    #[derivative(Default(value = "1.0"))]
+    pub traction: f32,
+
+    /// How fast the surface carries characters along it, positive is to the right
+    pub conveyor_speed: f32,
+}
+
+impl SurfaceMaterial {
+    /// Slippery, characters slide around and take a while to stop
+    pub fn ice() -> Self {
+        Self {
+            friction: 0.0,
+            speed_factor: 1.25,
+            traction: 0.1,
+            ..Default::default()
+        }
+    }
+
+    /// Sticky, characters move slowly
+    pub fn mud() -> Self {
+        Self {
+            friction: 1.0,
+            speed_factor: 0.4,
+            traction: 2.0,
+            ..Default::default()
+        }
+    }
+
+    /// Bounces characters back up
+    pub fn bouncy() -> Self {
+        Self {
+            restitution: 0.9,
+            ..Default::default()
+        }
+    }
+
+    /// Carries characters along at the given speed
+    pub fn conveyor(speed: f32) -> Self {
+        Self {
+            conveyor_speed: speed,
+            ..Default::default()
+        }
+    }
+
+    /// Returns the named preset (case insensitive), conveyors use the default conveyor speed
+    ///
+    /// Returns None if there's no such preset
+    pub fn preset(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "default" => Some(Self::default()),
+            "ice" => Some(Self::ice()),
+            "mud" => Some(Self::mud()),
+            "bouncy" => Some(Self::bouncy()),
+            "conveyor" => Some(Self::conveyor(3.0)),
+            _ => None,
+        }
+    }
+
+    /// Returns the collider friction
+    ///
+    /// The least friction wins so that ice is slippery for everything
+    pub fn collider_friction(&self) -> Friction {
+        Friction {
+            coefficient: self.friction,
+            combine_rule: CoefficientCombineRule::Min,
+        }
+    }
+
+    /// Returns the collider restitution
+    ///
+    /// The most restitution wins so that bouncy surfaces bounce everything
+    pub fn collider_restitution(&self) -> Restitution {
+        Restitution {
+            coefficient: self.restitution,
+            combine_rule: CoefficientCombineRule::Max,
+        }
+    }
+}
@@ -1,5 +1,6 @@
 //! Core herpa derp
 
+pub mod commands;
 pub mod components;
 pub mod events;
 pub mod resources;
@@ -1,8 +1,425 @@
 //! Input resources
 
+use std::collections::HashMap;
+
 use bevy::prelude::*;
 
 #[derive(Default)]
 pub struct CharacterInput2D {
     pub direction: Vec2,
 }
+
+/// Game actions that inputs can be bound to
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    Jump,
+    Dash,
+    Pause,
+    ToggleDebug,
+}
+
+impl Action {
+    /// Every action
+    pub const ALL: [Action; 8] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::Jump,
+        Action::Dash,
+        Action::Pause,
+        Action::ToggleDebug,
+    ];
+}
+
+/// An input that can be bound to an action
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum InputBinding {
+    Key(KeyCode),
+    GamepadButton(GamepadButtonType),
+}
+
+impl From<KeyCode> for InputBinding {
+    fn from(key: KeyCode) -> Self {
+        Self::Key(key)
+    }
+}
+
+impl From<GamepadButtonType> for InputBinding {
+    fn from(button: GamepadButtonType) -> Self {
+        Self::GamepadButton(button)
+    }
+}
+
+/// The device that input comes from
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum InputDevice {
+    Keyboard,
+    Gamepad(Gamepad),
+}
+
+impl Default for InputDevice {
+    fn default() -> Self {
+        Self::Keyboard
+    }
+}
+
+/// How analog stick input is shaped past the dead zone
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ResponseCurve {
+    Linear,
+    Quadratic,
+    Cubic,
+}
+
+impl Default for ResponseCurve {
+    fn default() -> Self {
+        Self::Linear
+    }
+}
+
+impl ResponseCurve {
+    /// Shapes an input value (0 - 1)
+    pub fn apply(&self, value: f32) -> f32 {
+        match self {
+            Self::Linear => value,
+            Self::Quadratic => value.powi(2),
+            Self::Cubic => value.powi(3),
+        }
+    }
+}
+
+/// Analog stick settings
+#[derive(Debug, Copy, Clone)]
+pub struct StickSettings {
+    /// How far the stick has to move before it counts (0 - 1)
+    pub dead_zone: f32,
+
+    /// How stick input is shaped past the dead zone
+    pub response_curve: ResponseCurve,
+}
+
+impl Default for StickSettings {
+    fn default() -> Self {
+        Self {
+            dead_zone: 0.2,
+            response_curve: ResponseCurve::Quadratic,
+        }
+    }
+}
+
+impl StickSettings {
+    /// Applies the dead zone and response curve to raw stick input
+    ///
+    /// The dead zone is radial and the input past it is rescaled to the full range
+    pub fn apply(&self, stick: Vec2) -> Vec2 {
+        let length = stick.length();
+        if length <= self.dead_zone {
+            return Vec2::ZERO;
+        }
+
+        let value = ((length - self.dead_zone) / (1.0 - self.dead_zone)).min(1.0);
+        stick / length * self.response_curve.apply(value)
+    }
+}
+
+/// Maps actions to their input bindings
+///
+/// An action can have any number of bindings, it's active while any of them are
+pub struct ActionMap {
+    bindings: HashMap<Action, Vec<InputBinding>>,
+
+    /// Movement analog stick settings
+    pub stick: StickSettings,
+}
+
+impl Default for ActionMap {
+    fn default() -> Self {
+        let mut map = Self {
+            bindings: HashMap::default(),
+            stick: StickSettings::default(),
+        };
+
+        // keyboard
+        map.bind(Action::MoveLeft, KeyCode::Left)
+            .bind(Action::MoveLeft, KeyCode::A)
+            .bind(Action::MoveRight, KeyCode::Right)
+            .bind(Action::MoveRight, KeyCode::D)
+            .bind(Action::MoveUp, KeyCode::Up)
+            .bind(Action::MoveUp, KeyCode::W)
+            .bind(Action::MoveDown, KeyCode::Down)
+            .bind(Action::MoveDown, KeyCode::S)
+            .bind(Action::Jump, KeyCode::Space)
+            .bind(Action::Dash, KeyCode::LShift)
+            .bind(Action::Pause, KeyCode::Escape)
+            .bind(Action::ToggleDebug, KeyCode::Grave);
+
+        // gamepad
+        map.bind(Action::MoveLeft, GamepadButtonType::DPadLeft)
+            .bind(Action::MoveRight, GamepadButtonType::DPadRight)
+            .bind(Action::MoveUp, GamepadButtonType::DPadUp)
+            .bind(Action::MoveDown, GamepadButtonType::DPadDown)
+            .bind(Action::Jump, GamepadButtonType::South)
+            .bind(Action::Dash, GamepadButtonType::West)
+            .bind(Action::Dash, GamepadButtonType::RightTrigger2)
+            .bind(Action::Pause, GamepadButtonType::Start);
+
+        map
+    }
+}
+
+impl ActionMap {
+    /// Adds a binding to an action
+    pub fn bind(&mut self, action: Action, binding: impl Into<InputBinding>) -> &mut Self {
+        let binding = binding.into();
+        let bindings = self.bindings.entry(action).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+        self
+    }
+
+    /// Removes a binding from an action
+    pub fn unbind(&mut self, action: Action, binding: impl Into<InputBinding>) -> &mut Self {
+        let binding = binding.into();
+        if let Some(bindings) = self.bindings.get_mut(&action) {
+            bindings.retain(|other| *other != binding);
+        }
+        self
+    }
+
+    /// Removes all of an action's bindings
+    pub fn clear(&mut self, action: Action) -> &mut Self {
+        self.bindings.remove(&action);
+        self
+    }
+
+    /// Returns an action's bindings
+    pub fn bindings(&self, action: Action) -> &[InputBinding] {
+        self.bindings
+            .get(&action)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+/// The current state of every action, updated from the action map every frame
+///
+/// Only the last used input device drives the actions
+#[derive(Default)]
+pub struct ActionState {
+    input: Input<Action>,
+    move_axis: Vec2,
+    device: InputDevice,
+}
+
+impl ActionState {
+    /// Returns the last used input device
+    pub fn device(&self) -> InputDevice {
+        self.device
+    }
+
+    /// Switches the input device
+    pub fn set_device(&mut self, device: InputDevice) {
+        if device != self.device {
+            info!("switching input to {:?}", device);
+        }
+        self.device = device;
+    }
+
+    /// Returns the analog movement input, with the dead zone and response curve applied
+    pub fn move_axis(&self) -> Vec2 {
+        self.move_axis
+    }
+
+    /// Sets the analog movement input
+    pub fn set_move_axis(&mut self, move_axis: Vec2) {
+        self.move_axis = move_axis;
+    }
+
+    /// Is the action active?
+    pub fn pressed(&self, action: Action) -> bool {
+        self.input.pressed(action)
+    }
+
+    /// Did the action just become active this frame?
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.input.just_pressed(action)
+    }
+
+    /// Did the action just stop being active this frame?
+    pub fn just_released(&self, action: Action) -> bool {
+        self.input.just_released(action)
+    }
+
+    /// Consumes an action press so nothing else sees it this frame
+    pub fn clear_just_pressed(&mut self, action: Action) -> bool {
+        self.input.clear_just_pressed(action)
+    }
+
+    /// Starts a new frame of action state
+    pub fn clear(&mut self) {
+        self.input.clear();
+    }
+
+    /// Sets whether the action is active or not
+    pub fn set(&mut self, action: Action, pressed: bool) {
+        if pressed {
+            self.input.press(action);
+        } else {
+            self.input.release(action);
+        }
+    }
+
+    /// Sets whether the action is active or not without reporting a press or release
+    pub fn set_silently(&mut self, action: Action, pressed: bool) {
+        self.set(action, pressed);
+        self.input.clear_just_pressed(action);
+        self.input.clear_just_released(action);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_bindings() {
+        let map = ActionMap::default();
+
+        for action in Action::ALL {
+            assert!(
+                !map.bindings(action).is_empty(),
+                "{:?} has no bindings",
+                action
+            );
+        }
+    }
+
+    #[test]
+    fn bind_once() {
+        let mut map = ActionMap::default();
+        let bindings = map.bindings(Action::Jump).len();
+
+        map.bind(Action::Jump, KeyCode::W)
+            .bind(Action::Jump, KeyCode::W);
+
+        assert_eq!(map.bindings(Action::Jump).len(), bindings + 1);
+        assert!(map
+            .bindings(Action::Jump)
+            .contains(&InputBinding::Key(KeyCode::W)));
+    }
+
+    #[test]
+    fn unbind() {
+        let mut map = ActionMap::default();
+
+        let bindings = map.bindings(Action::Jump).len();
+
+        map.unbind(Action::Jump, KeyCode::Space);
+
+        assert_eq!(map.bindings(Action::Jump).len(), bindings - 1);
+        assert!(!map
+            .bindings(Action::Jump)
+            .contains(&InputBinding::Key(KeyCode::Space)));
+
+        // unbinding something that isn't bound does nothing
+        map.unbind(Action::Jump, KeyCode::Space)
+            .unbind(Action::Jump, KeyCode::Q);
+        assert_eq!(map.bindings(Action::Jump).len(), bindings - 1);
+    }
+
+    #[test]
+    fn clear() {
+        let mut map = ActionMap::default();
+
+        map.clear(Action::Dash);
+        assert!(map.bindings(Action::Dash).is_empty());
+
+        map.bind(Action::Dash, KeyCode::X);
+        assert_eq!(map.bindings(Action::Dash), &[InputBinding::Key(KeyCode::X)]);
+    }
+
+    #[test]
+    fn release_idle_action() {
+        let mut state = ActionState::default();
+
+        state.set(Action::Jump, false);
+        assert!(!state.just_released(Action::Jump));
+
+        state.set(Action::Jump, true);
+        state.clear();
+        state.set(Action::Jump, false);
+        assert!(state.just_released(Action::Jump));
+    }
+
+    #[test]
+    fn response_curves() {
+        for curve in [
+            ResponseCurve::Linear,
+            ResponseCurve::Quadratic,
+            ResponseCurve::Cubic,
+        ] {
+            assert_eq!(curve.apply(0.0), 0.0);
+            assert_eq!(curve.apply(1.0), 1.0);
+        }
+
+        assert_eq!(ResponseCurve::Linear.apply(0.5), 0.5);
+        assert_eq!(ResponseCurve::Quadratic.apply(0.5), 0.25);
+        assert_eq!(ResponseCurve::Cubic.apply(0.5), 0.125);
+    }
+
+    #[test]
+    fn stick_dead_zone() {
+        let stick = StickSettings::default();
+
+        assert_eq!(stick.apply(Vec2::ZERO), Vec2::ZERO);
+        assert_eq!(stick.apply(Vec2::new(0.1, -0.1)), Vec2::ZERO);
+        assert_eq!(stick.apply(Vec2::X * stick.dead_zone), Vec2::ZERO);
+        assert_ne!(stick.apply(Vec2::X * (stick.dead_zone + 0.1)), Vec2::ZERO);
+    }
+
+    #[test]
+    fn stick_rescale() {
+        let stick = StickSettings {
+            dead_zone: 0.2,
+            response_curve: ResponseCurve::Linear,
+        };
+
+        // halfway between the dead zone and the edge
+        let value = stick.apply(Vec2::new(0.0, -0.6));
+        assert!((value - Vec2::new(0.0, -0.5)).length() < 1e-5);
+
+        // full deflection, past the edge is clamped
+        assert!((stick.apply(Vec2::X).length() - 1.0).abs() < 1e-5);
+        assert!((stick.apply(Vec2::ONE).length() - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn stick_keeps_direction() {
+        let stick = StickSettings::default();
+        let raw = Vec2::new(0.3, 0.4);
+
+        let value = stick.apply(raw);
+        assert!(value.length() < raw.length());
+        assert!((value.normalize() - raw.normalize()).length() < 1e-5);
+    }
+
+    #[test]
+    fn set_silently() {
+        let mut state = ActionState::default();
+
+        state.set_silently(Action::Jump, true);
+        assert!(state.pressed(Action::Jump));
+        assert!(!state.just_pressed(Action::Jump));
+
+        state.clear();
+        state.set_silently(Action::Jump, false);
+        assert!(!state.pressed(Action::Jump));
+        assert!(!state.just_released(Action::Jump));
+    }
+}
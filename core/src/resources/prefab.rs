@@ -0,0 +1,221 @@
+//! Prefab resources
+
+use std::collections::HashMap;
+
+use bevy::asset::{AssetLoader, LoadContext, LoadState, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use bevy_rapier2d::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::components::ability::*;
+use crate::components::character::*;
+use crate::components::world::*;
+
+/// Prefab sprite
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PrefabSprite {
+    /// The sprite color
+    pub color: Color,
+
+    /// The sprite size
+    pub size: Vec2,
+}
+
+/// Prefab rigidbody type
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum PrefabRigidBody {
+    Dynamic,
+    Fixed,
+    KinematicPositionBased,
+    KinematicVelocityBased,
+}
+
+impl From<PrefabRigidBody> for RigidBody {
+    fn from(rigid_body: PrefabRigidBody) -> Self {
+        match rigid_body {
+            PrefabRigidBody::Dynamic => RigidBody::Dynamic,
+            PrefabRigidBody::Fixed => RigidBody::Fixed,
+            PrefabRigidBody::KinematicPositionBased => RigidBody::KinematicPositionBased,
+            PrefabRigidBody::KinematicVelocityBased => RigidBody::KinematicVelocityBased,
+        }
+    }
+}
+
+/// Prefab collider shape
+#[derive(Debug, Copy, Clone, Deserialize, Serialize)]
+pub enum PrefabCollider {
+    Cuboid { half_extents: Vec2 },
+    Ball { radius: f32 },
+}
+
+impl PrefabCollider {
+    /// Creates the collider, optionally resized to fit the given full size
+    pub fn collider(&self, size: Option<Vec2>) -> Collider {
+        match (self, size) {
+            (Self::Cuboid { .. }, Some(size)) => Collider::cuboid(size.x / 2.0, size.y / 2.0),
+            (Self::Cuboid { half_extents }, None) => {
+                Collider::cuboid(half_extents.x, half_extents.y)
+            }
+            (Self::Ball { .. }, Some(size)) => Collider::ball(size.min_element() / 2.0),
+            (Self::Ball { radius }, None) => Collider::ball(*radius),
+        }
+    }
+}
+
+/// Prefab collision groups
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PrefabCollisionGroups {
+    /// The physics layers the collider is a member of
+    pub memberships: u32,
+
+    /// The physics layers the collider interacts with
+    pub filters: u32,
+}
+
+/// A named entity archetype
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Prefab {
+    /// The spawned entity name, defaults to the prefab name
+    pub name: Option<String>,
+
+    /// Sprite
+    pub sprite: Option<PrefabSprite>,
+
+    /// Rigidbody type
+    pub rigid_body: Option<PrefabRigidBody>,
+
+    /// Should rotation be locked?
+    pub lock_rotation: bool,
+
+    /// Additional rigidbody mass
+    pub mass: Option<f32>,
+
+    /// Collider shape
+    pub collider: Option<PrefabCollider>,
+
+    /// Is the collider a sensor?
+    pub sensor: bool,
+
+    /// Collider collision groups
+    pub collision_groups: Option<PrefabCollisionGroups>,
+
+    /// Should the entity track its velocity?
+    pub velocity: bool,
+
+    /// Should the entity be able to have forces applied to it?
+    pub external_forces: bool,
+
+    /// Character settings
+    pub character: Option<Character>,
+
+    /// Is this a player character?
+    pub player: bool,
+
+    /// Is this a non-player character?
+    pub non_player: bool,
+
+    /// Should the entity wrap around the edges of the world?
+    pub screen_wrap: bool,
+
+    /// Multi-jump ability
+    pub multi_jump: Option<MultiJump>,
+
+    /// Dash ability
+    pub dash: Option<Dash>,
+
+    /// Ability pickup
+    pub pickup: Option<AbilityPickup>,
+}
+
+/// A set of prefabs loaded from a .prefabs.ron file
+#[derive(Debug, Default, Clone, Deserialize, Serialize, TypeUuid)]
+#[uuid = "f2005df4-67f5-41d2-a63a-96e8ce661377"]
+#[serde(transparent)]
+pub struct PrefabSet {
+    /// The prefabs by name
+    pub prefabs: HashMap<String, Prefab>,
+}
+
+/// Loads prefab sets
+#[derive(Default)]
+pub struct PrefabSetLoader;
+
+impl AssetLoader for PrefabSetLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let prefabs = ron::de::from_bytes::<PrefabSet>(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(prefabs));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["prefabs.ron"]
+    }
+}
+
+/// All of the loaded prefabs
+#[derive(Default)]
+pub struct PrefabRegistry {
+    sets: Vec<Handle<PrefabSet>>,
+    prefabs: HashMap<String, Prefab>,
+    ready: bool,
+}
+
+impl PrefabRegistry {
+    /// Creates a registry from a list of prefab sets
+    pub fn new(sets: Vec<Handle<PrefabSet>>) -> Self {
+        Self {
+            sets,
+            ..Default::default()
+        }
+    }
+
+    /// Have all of the prefab sets been loaded?
+    pub fn is_ready(&self) -> bool {
+        self.ready
+    }
+
+    /// Gets a prefab by name
+    pub fn get(&self, name: impl AsRef<str>) -> Option<&Prefab> {
+        self.prefabs.get(name.as_ref())
+    }
+
+    /// Returns the sorted prefab names
+    pub fn names(&self) -> Vec<&str> {
+        let mut names = self.prefabs.keys().map(String::as_str).collect::<Vec<_>>();
+        names.sort_unstable();
+        names
+    }
+
+    /// Rebuilds the registry from the loaded prefab sets
+    ///
+    /// Later sets override earlier sets, sets that failed to load are skipped
+    pub fn rebuild(&mut self, assets: &Assets<PrefabSet>, asset_server: &AssetServer) {
+        self.prefabs.clear();
+        self.ready = true;
+
+        for handle in self.sets.iter() {
+            match assets.get(handle) {
+                Some(set) => self
+                    .prefabs
+                    .extend(set.prefabs.iter().map(|(k, v)| (k.clone(), v.clone()))),
+                None if asset_server.get_load_state(handle) == LoadState::Failed => {
+                    let path = asset_server
+                        .get_handle_path(handle)
+                        .map(|path| path.path().display().to_string())
+                        .unwrap_or_default();
+                    error!("failed to load prefab set {}, skipping it", path);
+                }
+                None => self.ready = false,
+            }
+        }
+    }
+}
@@ -1,21 +1,149 @@
 //! Input systems
 
 use bevy::prelude::*;
+use bevy::utils::HashSet;
 
-use crate::resources::input::CharacterInput2D;
+use crate::resources::input::*;
 
-/// 2D platformer keyboard input
-pub fn platformer_2d_keyboard_input(
+/// Returns a gamepad's raw left stick input
+fn left_stick(gamepad_axes: &Axis<GamepadAxis>, gamepad: Gamepad) -> Vec2 {
+    let axis = |axis_type| {
+        gamepad_axes
+            .get(GamepadAxis::new(gamepad, axis_type))
+            .unwrap_or_default()
+    };
+
+    Vec2::new(
+        axis(GamepadAxisType::LeftStickX),
+        axis(GamepadAxisType::LeftStickY),
+    )
+}
+
+/// Updates the action state from the action map bindings
+///
+/// Whichever device was used last drives the actions,
+/// gamepads that get unplugged hand input back to the keyboard.
+/// Devices are only switched by new presses or stick deflections,
+/// and actions that change just because the device changed don't report a press or release.
+/// This should run in PreUpdate, after the Bevy input systems
+#[allow(clippy::too_many_arguments)]
+pub fn update_action_state(
+    mut deflected_sticks: Local<HashSet<Gamepad>>,
     keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    mut gamepad_events: EventReader<GamepadEvent>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    action_map: Res<ActionMap>,
+    mut action_state: ResMut<ActionState>,
+) {
+    // hot-plugging
+    for event in gamepad_events.iter() {
+        match event.event_type {
+            GamepadEventType::Connected => info!("gamepad {:?} connected", event.gamepad),
+            GamepadEventType::Disconnected => {
+                info!("gamepad {:?} disconnected", event.gamepad);
+
+                deflected_sticks.remove(&event.gamepad);
+
+                if action_state.device() == InputDevice::Gamepad(event.gamepad) {
+                    action_state.set_device(InputDevice::Keyboard);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    // last used device, held input on another device doesn't take over
+    let previous_device = action_state.device();
+
+    for gamepad in gamepads.iter().copied() {
+        let deflected = action_map.stick.apply(left_stick(&gamepad_axes, gamepad)) != Vec2::ZERO;
+        let newly_deflected = if deflected {
+            deflected_sticks.insert(gamepad)
+        } else {
+            deflected_sticks.remove(&gamepad);
+            false
+        };
+
+        let used = newly_deflected
+            || gamepad_buttons
+                .get_just_pressed()
+                .any(|button| button.gamepad == gamepad);
+        if used {
+            action_state.set_device(InputDevice::Gamepad(gamepad));
+        }
+    }
+
+    if keyboard_input.get_just_pressed().next().is_some() {
+        action_state.set_device(InputDevice::Keyboard);
+    }
+
+    let device = action_state.device();
+    let switched = device != previous_device;
+
+    action_state.clear();
+
+    for action in Action::ALL {
+        let mut pressed = false;
+        let mut just_pressed = false;
+        for binding in action_map.bindings(action) {
+            match (binding, device) {
+                (InputBinding::Key(key), InputDevice::Keyboard) => {
+                    pressed |= keyboard_input.pressed(*key);
+                    just_pressed |= keyboard_input.just_pressed(*key);
+                }
+                (InputBinding::GamepadButton(button_type), InputDevice::Gamepad(gamepad)) => {
+                    let button = GamepadButton::new(gamepad, *button_type);
+                    pressed |= gamepad_buttons.pressed(button);
+                    just_pressed |= gamepad_buttons.just_pressed(button);
+                }
+                _ => (),
+            }
+        }
+
+        // the device switch itself isn't a press or release
+        if switched && !just_pressed {
+            action_state.set_silently(action, pressed);
+        } else {
+            action_state.set(action, pressed);
+        }
+    }
+
+    let move_axis = match device {
+        InputDevice::Keyboard => Vec2::ZERO,
+        InputDevice::Gamepad(gamepad) => action_map.stick.apply(left_stick(&gamepad_axes, gamepad)),
+    };
+    action_state.set_move_axis(move_axis);
+}
+
+/// 2D platformer input
+///
+/// Digital movement actions are used over analog stick movement
+pub fn platformer_2d_input(
+    action_state: Res<ActionState>,
     mut character_input: ResMut<CharacterInput2D>,
 ) {
     character_input.direction = Vec2::default();
 
-    if keyboard_input.pressed(KeyCode::Right) {
+    if action_state.pressed(Action::MoveRight) {
         character_input.direction.x += 1.0;
     }
 
-    if keyboard_input.pressed(KeyCode::Left) {
+    if action_state.pressed(Action::MoveLeft) {
         character_input.direction.x -= 1.0;
     }
+
+    if action_state.pressed(Action::MoveUp) {
+        character_input.direction.y += 1.0;
+    }
+
+    if action_state.pressed(Action::MoveDown) {
+        character_input.direction.y -= 1.0;
+    }
+
+    // fall back to the analog stick
+    if character_input.direction == Vec2::ZERO {
+        character_input.direction = action_state.move_axis();
+    }
 }
@@ -1,3 +1,4 @@
 //! ECS systems
 
 pub mod input;
+pub mod prefab;
@@ -0,0 +1,27 @@
+//! Prefab systems
+
+use bevy::prelude::*;
+
+use crate::resources::prefab::*;
+
+/// Rebuilds the prefab registry whenever a prefab set is (re)loaded
+///
+/// Failed loads don't send asset events,
+/// so the registry is also rebuilt every frame until it's ready
+pub fn update_prefab_registry(
+    mut registry: ResMut<PrefabRegistry>,
+    mut asset_events: EventReader<AssetEvent<PrefabSet>>,
+    assets: Res<Assets<PrefabSet>>,
+    asset_server: Res<AssetServer>,
+) {
+    let changed = asset_events.iter().count() > 0;
+    let was_ready = registry.is_ready();
+    if !changed && was_ready {
+        return;
+    }
+
+    registry.rebuild(&assets, &asset_server);
+    if changed || registry.is_ready() != was_ready {
+        debug!("prefab registry rebuilt, ready: {}", registry.is_ready());
+    }
+}